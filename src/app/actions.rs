@@ -1,11 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Display};
 use std::slice::Iter;
 
 use crate::inputs::key::Key;
+use crate::settings::KeyBindings;
+
+/// Keys of the first groups, in the order they appear in the test list
+static GROUP_KEYS: [Key; 18] = [
+    Key::Char('1'),
+    Key::Char('2'),
+    Key::Char('3'),
+    Key::Char('4'),
+    Key::Char('5'),
+    Key::Char('6'),
+    Key::Char('7'),
+    Key::Char('8'),
+    Key::Char('9'),
+    Key::Alt('1'),
+    Key::Alt('2'),
+    Key::Alt('3'),
+    Key::Alt('4'),
+    Key::Alt('5'),
+    Key::Alt('6'),
+    Key::Alt('7'),
+    Key::Alt('8'),
+    Key::Alt('9'),
+];
 
 /// We define all available action
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum Action {
     Quit,
     Run,
@@ -17,17 +40,54 @@ pub enum Action {
     DownList,
     ActivateValgrind,
     RunCheckstyle,
-    RunTaskOne,
-    RunTaskTwo,
-    RunTaskThree,
+    /// Runs the group with this index, executables and their sub-tasks
+    RunGroup(usize),
+    RunSelectedGroup,
+    WriteReport,
+    ShowHistory,
+    /// Marks the run selected in the history view, to compare it with another one
+    MarkRun,
+    /// Switches the test log between a unified and a side by side diff
+    ToggleDiffView,
+    NextDifference,
+    PreviousDifference,
+    PageUp,
+    PageDown,
+    FirstLine,
+    LastLine,
+    /// Expands the unchanged lines folded in the selected row of the test log
+    ExpandLines,
+    /// Opens the search prompt for the focused panel
+    Search,
+    NextMatch,
+    PreviousMatch,
+    ClearSearch,
+    /// Shows the input of the test next to the test log
+    ShowInput,
+    /// Shows the whitespace and invisible characters of the test log
+    ShowWhitespace,
+    /// Opens the ref and the output of the selected test in the diff tool
+    OpenDiffTool,
+    /// Opens a file of the selected test in the editor
+    EditInput,
+    EditOutput,
+    EditValgrind,
+    /// Shows or hides the help and logs panels
+    ShowHelp,
+    ShowLogs,
+    /// Resizes the help and logs panels
+    GrowHelp,
+    ShrinkHelp,
+    GrowLogs,
+    ShrinkLogs,
     // SendVMChecker,
     // OpenVMChecker,
 }
 
 impl Action {
-    /// All available actions
+    /// All available actions, besides the ones for running a group
     pub fn iterator() -> Iter<'static, Action> {
-        static ACTIONS: [Action; 13] = [
+        static ACTIONS: [Action; 38] = [
             Action::Quit,
             Action::Run,
             Action::RunFailed,
@@ -38,9 +98,34 @@ impl Action {
             Action::DownList,
             Action::ActivateValgrind,
             Action::RunCheckstyle,
-            Action::RunTaskOne,
-            Action::RunTaskTwo,
-            Action::RunTaskThree,
+            Action::RunSelectedGroup,
+            Action::WriteReport,
+            Action::ShowHistory,
+            Action::MarkRun,
+            Action::ToggleDiffView,
+            Action::NextDifference,
+            Action::PreviousDifference,
+            Action::PageUp,
+            Action::PageDown,
+            Action::FirstLine,
+            Action::LastLine,
+            Action::ExpandLines,
+            Action::Search,
+            Action::NextMatch,
+            Action::PreviousMatch,
+            Action::ClearSearch,
+            Action::ShowInput,
+            Action::ShowWhitespace,
+            Action::OpenDiffTool,
+            Action::EditInput,
+            Action::EditOutput,
+            Action::EditValgrind,
+            Action::ShowHelp,
+            Action::ShowLogs,
+            Action::GrowHelp,
+            Action::ShrinkHelp,
+            Action::GrowLogs,
+            Action::ShrinkLogs,
             // Action::SendVMChecker,
             // Action::OpenVMChecker,
         ];
@@ -48,7 +133,7 @@ impl Action {
     }
 
     /// List of key associated to action
-    pub fn keys(&self) -> &[Key] {
+    pub fn keys(&self) -> &'static [Key] {
         match self {
             Action::Quit => &[Key::Ctrl('c'), Key::Char('q')],
             Action::Run => &[Key::Char('r')],
@@ -60,15 +145,93 @@ impl Action {
             Action::DownList => &[Key::Down],
             Action::ActivateValgrind => &[Key::Char('v')],
             Action::RunCheckstyle => &[Key::Char('c')],
-            Action::RunTaskOne => &[Key::Char('1')],
-            Action::RunTaskTwo => &[Key::Char('2')],
-            Action::RunTaskThree => &[Key::Char('3')],
+            // Groups after the 18th can only be run with RunSelectedGroup
+            Action::RunGroup(index) => GROUP_KEYS
+                .get(*index)
+                .map(std::slice::from_ref)
+                .unwrap_or_default(),
+            Action::RunSelectedGroup => &[Key::Char('g')],
+            Action::WriteReport => &[Key::Char('h')],
+            Action::ShowHistory => &[Key::Char('H')],
+            Action::MarkRun => &[Key::Char('m')],
+            Action::ToggleDiffView => &[Key::Char('d')],
+            Action::NextDifference => &[Key::Char(']')],
+            Action::PreviousDifference => &[Key::Char('[')],
+            Action::PageUp => &[Key::PageUp],
+            Action::PageDown => &[Key::PageDown],
+            Action::FirstLine => &[Key::Home],
+            Action::LastLine => &[Key::End],
+            Action::ExpandLines => &[Key::Char('e')],
+            Action::Search => &[Key::Char('/')],
+            Action::NextMatch => &[Key::Char('n')],
+            Action::PreviousMatch => &[Key::Char('N')],
+            Action::ClearSearch => &[Key::Esc],
+            Action::ShowInput => &[Key::Char('i')],
+            Action::ShowWhitespace => &[Key::Char('w')],
+            Action::OpenDiffTool => &[Key::Char('D')],
+            Action::EditInput => &[Key::Char('I')],
+            Action::EditOutput => &[Key::Char('O')],
+            Action::EditValgrind => &[Key::Char('V')],
+            Action::ShowHelp => &[Key::Char('?')],
+            Action::ShowLogs => &[Key::Char('L')],
+            Action::GrowHelp => &[Key::Char('}')],
+            Action::ShrinkHelp => &[Key::Char('{')],
+            Action::GrowLogs => &[Key::Char('+')],
+            Action::ShrinkLogs => &[Key::Char('-')],
             // Action::SendVMChecker => &[Key::Char('p')],
             // Action::OpenVMChecker => &[Key::Char('o')],
         }
     }
 }
 
+impl Action {
+    /// Name of the action in the `[keys]` of the settings
+    pub fn name(&self) -> String {
+        let name = match self {
+            Action::Quit => "quit",
+            Action::Run => "run",
+            Action::RunFailed => "run_failed",
+            Action::RunCurrent => "run_current",
+            Action::RightList => "right_list",
+            Action::LeftList => "left_list",
+            Action::UpList => "up_list",
+            Action::DownList => "down_list",
+            Action::ActivateValgrind => "activate_valgrind",
+            Action::RunCheckstyle => "run_checkstyle",
+            Action::RunGroup(index) => return format!("run_group_{}", index + 1),
+            Action::RunSelectedGroup => "run_selected_group",
+            Action::WriteReport => "write_report",
+            Action::ShowHistory => "show_history",
+            Action::MarkRun => "mark_run",
+            Action::ToggleDiffView => "toggle_diff_view",
+            Action::NextDifference => "next_difference",
+            Action::PreviousDifference => "previous_difference",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::FirstLine => "first_line",
+            Action::LastLine => "last_line",
+            Action::ExpandLines => "expand_lines",
+            Action::Search => "search",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::ClearSearch => "clear_search",
+            Action::ShowInput => "show_input",
+            Action::ShowWhitespace => "show_whitespace",
+            Action::OpenDiffTool => "open_diff_tool",
+            Action::EditInput => "edit_input",
+            Action::EditOutput => "edit_output",
+            Action::EditValgrind => "edit_valgrind",
+            Action::ShowHelp => "show_help",
+            Action::ShowLogs => "show_logs",
+            Action::GrowHelp => "grow_help",
+            Action::ShrinkHelp => "shrink_help",
+            Action::GrowLogs => "grow_logs",
+            Action::ShrinkLogs => "shrink_logs",
+        };
+        name.to_string()
+    }
+}
+
 /// Could display a user friendly short description of action
 impl Display for Action {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -83,9 +246,35 @@ impl Display for Action {
             Action::DownList => "Go down the list",
             Action::ActivateValgrind => "Turn On/Off valgrind",
             Action::RunCheckstyle => "Run checkstyle",
-            Action::RunTaskOne => "Run task-1",
-            Action::RunTaskTwo => "Run task-2",
-            Action::RunTaskThree => "Run task-3",
+            Action::RunGroup(index) => return write!(f, "Run group {}", index + 1),
+            Action::RunSelectedGroup => "Run test's group",
+            Action::WriteReport => "Write HTML report",
+            Action::ShowHistory => "Show/Hide run history",
+            Action::MarkRun => "Compare runs in history",
+            Action::ToggleDiffView => "Side by side/unified diff",
+            Action::NextDifference => "Next difference",
+            Action::PreviousDifference => "Previous difference",
+            Action::PageUp => "Page up the log",
+            Action::PageDown => "Page down the log",
+            Action::FirstLine => "Start of the log",
+            Action::LastLine => "End of the log",
+            Action::ExpandLines => "Expand/fold unchanged lines",
+            Action::Search => "Search the focused panel",
+            Action::NextMatch => "Next match",
+            Action::PreviousMatch => "Previous match",
+            Action::ClearSearch => "Clear the search",
+            Action::ShowInput => "Show/Hide the input",
+            Action::ShowWhitespace => "Show/Hide whitespace",
+            Action::OpenDiffTool => "Open in the diff tool",
+            Action::EditInput => "Edit the input",
+            Action::EditOutput => "Edit the output",
+            Action::EditValgrind => "Edit the valgrind log",
+            Action::ShowHelp => "Show/Hide the help",
+            Action::ShowLogs => "Show/Hide the logs",
+            Action::GrowHelp => "Widen the help",
+            Action::ShrinkHelp => "Narrow the help",
+            Action::GrowLogs => "Taller logs",
+            Action::ShrinkLogs => "Shorter logs",
             // Action::SendVMChecker => "Send homework to vmchecker",
             // Action::OpenVMChecker => "Check vmchecker output",
         };
@@ -93,61 +282,169 @@ impl Display for Action {
     }
 }
 
-/// The application should have some contextual actions.
+/// The keys of the actions, the ones of [`Action::keys`] unless the user set others
 #[derive(Default, Debug, Clone)]
-pub struct Actions(Vec<Action>);
+pub struct Keymap(HashMap<Action, Vec<Key>>);
 
-impl Actions {
-    /// Given a key, find the corresponding action
-    pub fn find(&self, key: Key) -> Option<&Action> {
-        Action::iterator()
-            .filter(|action| self.0.contains(action))
-            .find(|action| action.keys().contains(&key))
-    }
+impl Keymap {
+    /// Reads the keys set by the user for the actions, with `groups` groups to run.
+    ///
+    /// Returns every problem found, such as a key bound to two actions.
+    pub fn new(
+        bindings: &BTreeMap<String, KeyBindings>,
+        groups: usize,
+    ) -> Result<Self, Vec<String>> {
+        let actions: Vec<Action> = Action::iterator()
+            .copied()
+            .chain((0..groups).map(Action::RunGroup))
+            .collect();
+        let mut problems = Vec::new();
 
-    /// Get contextual actions.
-    /// (just for building a help view)
-    pub fn actions(&self) -> &[Action] {
-        self.0.as_slice()
-    }
-}
+        let mut keymap = HashMap::new();
+        for (name, keys) in bindings.iter() {
+            let Some(action) = actions.iter().find(|action| action.name() == *name) else {
+                problems.push(format!("Unknown action \"{}\"", name));
+                continue;
+            };
 
-impl From<Vec<Action>> for Actions {
-    /// Build contextual action
-    ///
-    /// # Panics
-    ///
-    /// If two actions have same key
-    fn from(actions: Vec<Action>) -> Self {
-        // Check key unicity
-        let mut map: HashMap<Key, Vec<Action>> = HashMap::new();
-        for action in actions.iter() {
-            for key in action.keys().iter() {
-                match map.get_mut(key) {
-                    Some(vec) => vec.push(*action),
-                    None => {
-                        map.insert(*key, vec![*action]);
-                    }
+            let mut parsed = Vec::new();
+            for key in keys.keys() {
+                match key.parse() {
+                    Ok(key) => parsed.push(key),
+                    Err(err) => problems.push(format!("{} for {}", err, name)),
                 }
             }
+            keymap.insert(*action, parsed);
+        }
+        let keymap = Self(keymap);
+
+        let mut bound: HashMap<Key, Vec<Action>> = HashMap::new();
+        for action in actions.iter() {
+            for key in keymap.keys(action) {
+                bound.entry(*key).or_default().push(*action);
+            }
         }
-        let errors = map
+        let mut conflicts: Vec<String> = bound
             .iter()
-            .filter(|(_, actions)| actions.len() > 1) // at least two actions share same shortcut
+            .filter(|(_, actions)| actions.len() > 1)
             .map(|(key, actions)| {
                 let actions = actions
                     .iter()
-                    .map(Action::to_string)
+                    .map(Action::name)
                     .collect::<Vec<_>>()
                     .join(", ");
-                format!("Conflict key {} with actions {}", key, actions)
+                format!("{} is bound to {}", key, actions)
             })
-            .collect::<Vec<_>>();
-        if !errors.is_empty() {
-            panic!("{}", errors.join("; "))
+            .collect();
+        conflicts.sort();
+        problems.append(&mut conflicts);
+
+        if problems.is_empty() {
+            Ok(keymap)
+        } else {
+            Err(problems)
+        }
+    }
+
+    pub fn keys(&self, action: &Action) -> &[Key] {
+        match self.0.get(action) {
+            Some(keys) => keys,
+            None => action.keys(),
+        }
+    }
+}
+
+/// The application should have some contextual actions.
+#[derive(Default, Debug, Clone)]
+pub struct Actions {
+    actions: Vec<Action>,
+    keymap: Keymap,
+}
+
+impl Actions {
+    /// The actions of the current state, with the keys of the keymap, which was
+    /// checked for conflicts
+    pub fn new(actions: Vec<Action>, keymap: &Keymap) -> Self {
+        Self {
+            actions,
+            keymap: keymap.clone(),
         }
+    }
+
+    /// Given a key, find the corresponding action
+    pub fn find(&self, key: Key) -> Option<&Action> {
+        self.actions
+            .iter()
+            .find(|action| self.keys(action).contains(&key))
+    }
+
+    /// Get contextual actions.
+    /// (just for building a help view)
+    pub fn actions(&self) -> &[Action] {
+        self.actions.as_slice()
+    }
+
+    /// The keys the action is bound to
+    pub fn keys(&self, action: &Action) -> &[Key] {
+        self.keymap.keys(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(keys: &[(&str, &[&str])]) -> BTreeMap<String, KeyBindings> {
+        keys.iter()
+            .map(|(action, keys)| {
+                let keys = keys.iter().map(|key| key.to_string()).collect();
+                (action.to_string(), KeyBindings::Many(keys))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_keys_do_not_conflict() {
+        assert!(Keymap::new(&BTreeMap::new(), GROUP_KEYS.len()).is_ok());
+    }
+
+    #[test]
+    fn keys_set_by_the_user_replace_the_default_ones() {
+        let keymap = Keymap::new(&bindings(&[("quit", &["x"]), ("run", &["q"])]), 0).unwrap();
+
+        assert_eq!(keymap.keys(&Action::Quit), [Key::Char('x')]);
+        assert_eq!(keymap.keys(&Action::Run), [Key::Char('q')]);
+        assert_eq!(keymap.keys(&Action::RunFailed), [Key::Char('f')]);
+    }
+
+    #[test]
+    fn finds_keys_bound_twice() {
+        let problems = Keymap::new(&bindings(&[("run", &["q", "ctrl+c"])]), 0).unwrap_err();
+
+        assert_eq!(
+            problems,
+            [
+                "<Ctrl+c> is bound to quit, run",
+                "<q> is bound to quit, run"
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_unknown_actions_and_keys() {
+        let problems = Keymap::new(
+            &bindings(&[("run_group_1", &["j"]), ("run", &["hyper+r"])]),
+            0,
+        )
+        .unwrap_err();
 
-        // Ok, we can create contextual actions
-        Self(actions)
+        assert_eq!(
+            problems,
+            [
+                "Unknown key \"hyper+r\" for run",
+                "Unknown action \"run_group_1\""
+            ]
+        );
+        assert!(Keymap::new(&bindings(&[("run_group_1", &["j"])]), 1).is_ok());
     }
 }
@@ -0,0 +1,361 @@
+use similar::{ChangeTag, TextDiff};
+
+use crate::report::DIFF_TIMEOUT;
+
+/// A row of a side by side diff, with the line of the ref and the line of the output
+/// shown next to it. Each side is its line number, from 1, and the index of the line in
+/// the [`diff_lines`](crate::report::diff_lines) the row was laid out from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRow {
+    pub reference: Option<(usize, usize)>,
+    pub output: Option<(usize, usize)>,
+}
+
+impl DiffRow {
+    /// The line number and the text of the ref and of the output
+    pub fn sides<'a>(&self, diff: &'a [(&'static str, String)]) -> [Option<(usize, &'a str)>; 2] {
+        [self.reference, self.output]
+            .map(|side| side.map(|(number, index)| (number, diff[index].1.as_str())))
+    }
+
+    /// The ref and the output differ on this row
+    pub fn changed(&self, diff: &[(&'static str, String)]) -> bool {
+        !matches!(self.sides(diff), [Some((_, a)), Some((_, b))] if a == b)
+    }
+
+    /// The row changed, but only in whitespace or invisible characters
+    pub fn whitespace_only(&self, diff: &[(&'static str, String)]) -> bool {
+        if !self.changed(diff) {
+            return false;
+        }
+
+        match self.sides(diff) {
+            [Some((_, reference)), Some((_, output))] => printed(reference) == printed(output),
+            [Some((_, line)), None] | [None, Some((_, line))] => printed(line).is_empty(),
+            [None, None] => false,
+        }
+    }
+}
+
+/// Lays the lines of [`diff_lines`](crate::report::diff_lines) out in two columns, the lines missing from the
+/// output next to the lines printed instead of them
+pub fn side_by_side(diff: &[(&'static str, String)]) -> Vec<DiffRow> {
+    let mut rows = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut reference_line, mut output_line) = (0, 0);
+
+    for (index, (sign, _)) in diff.iter().enumerate() {
+        match *sign {
+            "-" => {
+                reference_line += 1;
+                removed.push((reference_line, index));
+            }
+            "+" => {
+                output_line += 1;
+                added.push((output_line, index));
+            }
+            _ => {
+                pair_changes(&mut rows, &mut removed, &mut added);
+                reference_line += 1;
+                output_line += 1;
+                rows.push(DiffRow {
+                    reference: Some((reference_line, index)),
+                    output: Some((output_line, index)),
+                });
+            }
+        }
+    }
+    pair_changes(&mut rows, &mut removed, &mut added);
+
+    rows
+}
+
+fn pair_changes(
+    rows: &mut Vec<DiffRow>,
+    removed: &mut Vec<(usize, usize)>,
+    added: &mut Vec<(usize, usize)>,
+) {
+    let count = removed.len().max(added.len());
+    let mut removed = removed.drain(..);
+    let mut added = added.drain(..);
+
+    for _ in 0..count {
+        rows.push(DiffRow {
+            reference: removed.next(),
+            output: added.next(),
+        });
+    }
+}
+
+/// For every line of [`diff_lines`](crate::report::diff_lines), the line of the ref it stands for, from 0. Lines
+/// only in the output stand for the line of the ref printed before them.
+pub fn ref_positions(diff: &[(&'static str, String)]) -> Vec<usize> {
+    let mut next = 0usize;
+
+    diff.iter()
+        .map(|(sign, _)| match *sign {
+            "+" => next.saturating_sub(1),
+            _ => {
+                next += 1;
+                next - 1
+            }
+        })
+        .collect()
+}
+
+/// Like [`ref_positions`], for the rows of [`side_by_side`]
+pub fn row_ref_positions(rows: &[DiffRow]) -> Vec<usize> {
+    let mut next = 0usize;
+
+    rows.iter()
+        .map(|row| match row.reference {
+            Some((number, _)) => {
+                next = number;
+                number - 1
+            }
+            None => next.saturating_sub(1),
+        })
+        .collect()
+}
+
+/// For every line of [`diff_lines`](crate::report::diff_lines), the index of the line that replaced it or that it
+/// replaced, the n-th removed line of a change going with its n-th added line
+pub fn line_pairs(diff: &[(&'static str, String)]) -> Vec<Option<usize>> {
+    let mut pairs = vec![None; diff.len()];
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+
+    for (index, (sign, _)) in diff.iter().enumerate() {
+        match *sign {
+            "-" => removed.push(index),
+            "+" => added.push(index),
+            _ => {
+                for (&old, &new) in removed.iter().zip(added.iter()) {
+                    pairs[old] = Some(new);
+                    pairs[new] = Some(old);
+                }
+                removed.clear();
+                added.clear();
+            }
+        }
+    }
+    for (&old, &new) in removed.iter().zip(added.iter()) {
+        pairs[old] = Some(new);
+        pairs[new] = Some(old);
+    }
+
+    pairs
+}
+
+/// For every line of [`diff_lines`](crate::report::diff_lines), whether it changed only in whitespace or invisible
+/// characters, compared to the line printed instead of it
+pub fn whitespace_only(diff: &[(&'static str, String)]) -> Vec<bool> {
+    let pairs = line_pairs(diff);
+
+    diff.iter()
+        .zip(pairs)
+        .map(|((sign, line), pair)| match pair {
+            _ if *sign == " " => false,
+            Some(pair) => printed(line) == printed(&diff[pair].1),
+            None => printed(line).is_empty(),
+        })
+        .collect()
+}
+
+/// The glyph showing an invisible character, or `None` if it can be seen already. Line
+/// breaks are left to the `⏎` added by [`diff_lines`](crate::report::diff_lines)
+pub fn whitespace_glyph(c: char) -> Option<char> {
+    match c {
+        '\n' => None,
+        ' ' => Some('·'),
+        '\t' => Some('→'),
+        '\r' => Some('␍'),
+        '\u{a0}' | '\u{202f}' => Some('⍽'),
+        '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}' => Some('¤'),
+        '\u{7f}' => Some('␡'),
+        // Control pictures, from ␀ to ␟
+        c if c < ' ' => char::from_u32(0x2400 + c as u32),
+        _ => None,
+    }
+}
+
+/// The characters of a line that can be seen
+fn printed(line: &str) -> String {
+    line.chars()
+        .filter(|&c| c != '⏎' && !c.is_whitespace() && whitespace_glyph(c).is_none())
+        .collect()
+}
+
+/// The words of a changed line, marked `true` when they differ from the other line
+pub type InlineChanges = Vec<(bool, String)>;
+
+/// Finds the words that changed between a line of the ref and the line of the output
+/// printed instead of it. Lines with little in common are left without highlights,
+/// since every word of them would be marked.
+pub fn inline_diff(reference: &str, output: &str) -> Option<(InlineChanges, InlineChanges)> {
+    let diff = TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_words(reference, output);
+    if diff.ratio() < 0.5 {
+        return None;
+    }
+
+    let mut removed: InlineChanges = Vec::new();
+    let mut added: InlineChanges = Vec::new();
+    for change in diff.iter_all_changes() {
+        let (changed, side) = match change.tag() {
+            ChangeTag::Equal => {
+                push_word(&mut removed, false, change.value());
+                (false, &mut added)
+            }
+            ChangeTag::Delete => (true, &mut removed),
+            ChangeTag::Insert => (true, &mut added),
+        };
+        push_word(side, changed, change.value());
+    }
+
+    Some((removed, added))
+}
+
+/// Merges the word with the previous one, when both are marked the same
+fn push_word(words: &mut InlineChanges, changed: bool, word: &str) {
+    match words.last_mut() {
+        Some((last_changed, last)) if *last_changed == changed => last.push_str(word),
+        _ => words.push((changed, word.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::diff_lines;
+
+    #[test]
+    fn rows_point_into_the_diff() {
+        let diff = diff_lines("a\nb\nc\n", "a\nB \nx\nc\n");
+        let rows = side_by_side(&diff);
+
+        assert_eq!(
+            rows,
+            [
+                DiffRow {
+                    reference: Some((1, 0)),
+                    output: Some((1, 0)),
+                },
+                DiffRow {
+                    reference: Some((2, 1)),
+                    output: Some((2, 2)),
+                },
+                DiffRow {
+                    reference: None,
+                    output: Some((3, 3)),
+                },
+                DiffRow {
+                    reference: Some((3, 4)),
+                    output: Some((4, 4)),
+                },
+            ]
+        );
+        assert_eq!(
+            rows[1].sides(&diff),
+            [Some((2, "b\n⏎")), Some((2, "B \n⏎"))]
+        );
+        assert_eq!(
+            rows.iter()
+                .map(|row| row.changed(&diff))
+                .collect::<Vec<_>>(),
+            [false, true, true, false]
+        );
+        assert_eq!(row_ref_positions(&rows), [0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn changes_of_different_lengths_are_aligned() {
+        let diff = diff_lines("a\nb\nc\nd\n", "a\nx\nd\ne\n");
+        let rows = side_by_side(&diff);
+
+        let numbers: Vec<(Option<usize>, Option<usize>)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.reference.map(|(number, _)| number),
+                    row.output.map(|(number, _)| number),
+                )
+            })
+            .collect();
+        assert_eq!(
+            numbers,
+            [
+                (Some(1), Some(1)),
+                // b was replaced by x, c was removed
+                (Some(2), Some(2)),
+                (Some(3), None),
+                (Some(4), Some(3)),
+                (None, Some(4)),
+            ]
+        );
+        assert_eq!(row_ref_positions(&rows), [0, 1, 2, 3, 3]);
+        assert_eq!(ref_positions(&diff), [0, 1, 2, 2, 3, 3]);
+        assert_eq!(
+            line_pairs(&diff),
+            [None, Some(3), None, Some(1), None, None]
+        );
+    }
+
+    #[test]
+    fn finds_whitespace_only_changes() {
+        let diff = diff_lines("a b\nc\n", "a  b\nd\n\t\n");
+        let signs: Vec<&str> = diff.iter().map(|(sign, _)| *sign).collect();
+        assert_eq!(signs, ["-", "-", "+", "+", "+"]);
+
+        // The extra tab is not paired with a line of the ref
+        assert_eq!(whitespace_only(&diff), [true, false, true, false, true]);
+
+        let rows = side_by_side(&diff);
+        assert_eq!(
+            rows.iter()
+                .map(|row| row.whitespace_only(&diff))
+                .collect::<Vec<_>>(),
+            [true, false, true]
+        );
+    }
+
+    #[test]
+    fn invisible_characters_have_a_glyph() {
+        assert_eq!(whitespace_glyph(' '), Some('·'));
+        assert_eq!(whitespace_glyph('\t'), Some('→'));
+        assert_eq!(whitespace_glyph('\u{1b}'), Some('␛'));
+        assert_eq!(whitespace_glyph('\u{feff}'), Some('¤'));
+        assert_eq!(whitespace_glyph('\n'), None);
+        assert_eq!(whitespace_glyph('a'), None);
+        assert_eq!(printed("a\u{a0}b \r\n⏎"), "ab");
+    }
+
+    #[test]
+    fn highlights_the_changed_words() {
+        let changed = |words: &[(bool, &str)]| -> InlineChanges {
+            words
+                .iter()
+                .map(|(changed, word)| (*changed, word.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            inline_diff("total 10 points", "total 12 points"),
+            Some((
+                changed(&[(false, "total "), (true, "10"), (false, " points")]),
+                changed(&[(false, "total "), (true, "12"), (false, " points")]),
+            ))
+        );
+        assert_eq!(
+            inline_diff("a b", "a b c"),
+            Some((
+                changed(&[(false, "a b")]),
+                changed(&[(false, "a b"), (true, " c")]),
+            ))
+        );
+        // Nothing in common
+        assert_eq!(inline_diff("one two", "three four"), None);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use super::diff::{
+    line_pairs, ref_positions, row_ref_positions, side_by_side, whitespace_only, DiffRow,
+};
+use crate::report::diff_lines;
+
+/// Diffs kept for the tests selected last
+const CAPACITY: usize = 16;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The diff of an output against its ref, with everything the test log needs from it
+/// computed once
+#[derive(Debug, Default)]
+pub struct TestDiff {
+    /// Tells the diffs apart, the test log is only laid out again for a new one
+    pub id: u64,
+    pub lines: Vec<(&'static str, String)>,
+    /// The side by side layout of `lines`
+    pub rows: Vec<DiffRow>,
+    pub changed_lines: Vec<bool>,
+    pub changed_rows: Vec<bool>,
+    /// The line each changed line is compared to, see [`line_pairs`]
+    pub pairs: Vec<Option<usize>>,
+    /// The lines that changed only in whitespace
+    pub whitespace: Vec<bool>,
+    /// Every difference is in whitespace
+    pub whitespace_only: bool,
+    /// The line of the ref each line and row stands for
+    pub ref_positions: Vec<usize>,
+    pub row_ref_positions: Vec<usize>,
+}
+
+impl TestDiff {
+    pub fn new(reference: &str, output: &str) -> Self {
+        let lines = diff_lines(reference, output);
+        let rows = side_by_side(&lines);
+        let changed_lines: Vec<bool> = lines.iter().map(|(sign, _)| *sign != " ").collect();
+        let whitespace = whitespace_only(&lines);
+
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            changed_rows: rows.iter().map(|row| row.changed(&lines)).collect(),
+            pairs: line_pairs(&lines),
+            whitespace_only: changed_lines.contains(&true) && whitespace == changed_lines,
+            ref_positions: ref_positions(&lines),
+            row_ref_positions: row_ref_positions(&rows),
+            changed_lines,
+            whitespace,
+            lines,
+            rows,
+        }
+    }
+}
+
+/// The diffs of the tests selected last, each computed once per result, by
+/// `(test_index, exec_index)`
+#[derive(Debug, Default)]
+pub struct DiffCache {
+    diffs: HashMap<(usize, usize), Arc<TestDiff>>,
+    /// The tests with a diff, the one used last at the back
+    used: VecDeque<(usize, usize)>,
+    /// Tests whose diff is being computed, and whether their output changed since
+    pending: HashMap<(usize, usize), bool>,
+}
+
+impl DiffCache {
+    pub fn get(&mut self, test: (usize, usize)) -> Option<Arc<TestDiff>> {
+        let diff = self.diffs.get(&test)?;
+        self.used.retain(|&used| used != test);
+        self.used.push_back(test);
+
+        Some(Arc::clone(diff))
+    }
+
+    pub fn contains(&self, test: (usize, usize)) -> bool {
+        self.diffs.contains_key(&test)
+    }
+
+    /// Marks the diff of the test as being computed, returning false if it already is
+    pub fn start(&mut self, test: (usize, usize)) -> bool {
+        if self.pending.contains_key(&test) {
+            return false;
+        }
+
+        self.pending.insert(test, false);
+        true
+    }
+
+    /// Stores the diff computed for the test, unless its output changed in the meantime.
+    /// An outdated diff is given back as an error, to be shown until the next one.
+    pub fn finish(
+        &mut self,
+        test: (usize, usize),
+        diff: TestDiff,
+    ) -> Result<Arc<TestDiff>, Arc<TestDiff>> {
+        let diff = Arc::new(diff);
+        if self.pending.remove(&test) != Some(false) {
+            return Err(diff);
+        }
+
+        if self.used.len() == CAPACITY {
+            if let Some(oldest) = self.used.pop_front() {
+                self.diffs.remove(&oldest);
+            }
+        }
+        self.diffs.insert(test, Arc::clone(&diff));
+        self.used.push_back(test);
+
+        Ok(diff)
+    }
+
+    /// The diff of the test could not be computed
+    pub fn cancel(&mut self, test: (usize, usize)) {
+        self.pending.remove(&test);
+    }
+
+    /// Forgets the diff of the test, as its output changed
+    pub fn invalidate(&mut self, test: (usize, usize)) {
+        self.diffs.remove(&test);
+        self.used.retain(|&used| used != test);
+        if let Some(outdated) = self.pending.get_mut(&test) {
+            *outdated = true;
+        }
+    }
+}
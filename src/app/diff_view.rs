@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Unchanged lines kept around every difference when the rest is folded
+const CONTEXT: usize = 3;
+
+/// A row of the test log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffEntry {
+    /// The line, or side by side row, with this index
+    Line(usize),
+    /// A run of unchanged lines, folded into one row until it is expanded
+    Unchanged { first: usize, count: usize },
+}
+
+/// What the rows are laid out from, they are only laid out again once it changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutSource {
+    /// The id of the diff
+    pub diff: u64,
+    pub side_by_side: bool,
+    /// The search in the test log and whether it is case sensitive
+    pub search: Option<(String, bool)>,
+}
+
+/// The rows shown in the test log, with the unchanged lines far from any difference
+/// folded, and where each difference starts
+#[derive(Debug, Clone, Default)]
+pub struct DiffView {
+    /// Folded runs the user expanded, by their first line
+    expanded: HashSet<usize>,
+    source: Option<LayoutSource>,
+    pub rows: Vec<DiffEntry>,
+    /// Rows where a difference starts
+    pub hunks: Vec<usize>,
+    /// Rows that fit in the test log, set when it is drawn
+    pub page: usize,
+    /// First row shown
+    pub offset: usize,
+}
+
+impl DiffView {
+    /// The rows are laid out from this already
+    pub fn is_laid_out(&self, source: &LayoutSource) -> bool {
+        self.source.as_ref() == Some(source)
+    }
+
+    /// Lays out the rows of a diff, given which of its lines changed and which match
+    /// the search, both kept out of the folds
+    pub fn layout(&mut self, source: LayoutSource, changed: &[bool], matched: &[bool]) {
+        if self.source.as_ref().map(|laid_out| laid_out.diff) != Some(source.diff) {
+            self.offset = 0;
+        }
+        self.source = Some(source);
+        self.rows.clear();
+        self.hunks.clear();
+        // The output of a passed test is shown whole
+        let fold = changed.contains(&true);
+        let shown = |line: usize| changed[line] || matched.get(line).copied().unwrap_or(false);
+
+        let mut line = 0;
+        while line < changed.len() {
+            if shown(line) {
+                if changed[line] && (line == 0 || !changed[line - 1]) {
+                    self.hunks.push(self.rows.len());
+                }
+                self.rows.push(DiffEntry::Line(line));
+                line += 1;
+                continue;
+            }
+
+            let end = (line..changed.len())
+                .find(|&line| shown(line))
+                .unwrap_or(changed.len());
+            let before = if line == 0 { 0 } else { CONTEXT };
+            let after = if end == changed.len() { 0 } else { CONTEXT };
+            let first = line + before;
+
+            if fold && end - line > before + after + 1 && !self.expanded.contains(&first) {
+                let last = end - after;
+                self.rows.extend((line..first).map(DiffEntry::Line));
+                self.rows.push(DiffEntry::Unchanged {
+                    first,
+                    count: last - first,
+                });
+                self.rows.extend((last..end).map(DiffEntry::Line));
+            } else {
+                self.rows.extend((line..end).map(DiffEntry::Line));
+            }
+            line = end;
+        }
+    }
+
+    /// Expands the folded lines on this row, returning false if there are none
+    pub fn expand(&mut self, row: usize) -> bool {
+        match self.rows.get(row) {
+            Some(&DiffEntry::Unchanged { first, .. }) => {
+                self.source = None;
+                self.expanded.insert(first)
+            }
+            _ => false,
+        }
+    }
+
+    /// Folds every expanded run again, also needed once the lines change
+    pub fn collapse(&mut self) {
+        self.expanded.clear();
+        self.source = None;
+    }
+
+    /// Scrolls as little as needed to show the selected row, returning the rows shown
+    pub fn window(&mut self, selected: Option<usize>) -> Range<usize> {
+        let window = scroll(self.offset, selected, self.page, self.rows.len());
+        self.offset = window.start;
+
+        window
+    }
+
+    /// The row of the first difference after the selected row
+    pub fn next_hunk(&self, selected: usize) -> Option<usize> {
+        self.hunks.iter().copied().find(|&row| row > selected)
+    }
+
+    /// The row of the first difference before the selected row
+    pub fn previous_hunk(&self, selected: usize) -> Option<usize> {
+        self.hunks.iter().copied().rev().find(|&row| row < selected)
+    }
+
+    /// The number, from 1, of the difference shown at the selected row
+    pub fn current_hunk(&self, selected: usize) -> Option<usize> {
+        self.hunks
+            .iter()
+            .rposition(|&row| row <= selected)
+            .map(|index| index + 1)
+    }
+}
+
+/// The rows of a list shown from `offset`, scrolled as little as needed to show the
+/// selected row
+pub fn scroll(offset: usize, selected: Option<usize>, page: usize, len: usize) -> Range<usize> {
+    let page = page.max(1);
+    let mut offset = offset;
+    if let Some(selected) = selected {
+        if selected < offset {
+            offset = selected;
+        } else if selected >= offset + page {
+            offset = selected + 1 - page;
+        }
+    }
+    offset = offset.min(len.saturating_sub(page));
+
+    offset..len.min(offset + page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(diff: u64) -> LayoutSource {
+        LayoutSource {
+            diff,
+            side_by_side: false,
+            search: None,
+        }
+    }
+
+    #[test]
+    fn folds_the_lines_far_from_the_changes() {
+        let mut changed = vec![false; 20];
+        changed[10] = true;
+        let mut view = DiffView::default();
+        view.layout(source(0), &changed, &[]);
+
+        let mut rows = vec![DiffEntry::Unchanged { first: 0, count: 7 }];
+        rows.extend((7..14).map(DiffEntry::Line));
+        rows.push(DiffEntry::Unchanged {
+            first: 14,
+            count: 6,
+        });
+        assert_eq!(view.rows, rows);
+        assert_eq!(view.hunks, [4]);
+        assert_eq!(view.next_hunk(0), Some(4));
+        assert_eq!(view.previous_hunk(4), None);
+        assert_eq!(view.current_hunk(6), Some(1));
+
+        // Lines matching the search are kept out of the folds
+        let mut matched = vec![false; 20];
+        matched[2] = true;
+        view.layout(source(0), &changed, &matched);
+        assert_eq!(
+            view.rows[..3],
+            [DiffEntry::Line(0), DiffEntry::Line(1), DiffEntry::Line(2)]
+        );
+
+        assert!(view.expand(view.rows.len() - 1));
+        view.layout(source(0), &changed, &[]);
+        assert_eq!(view.rows.last(), Some(&DiffEntry::Line(19)));
+        assert!(!view.expand(1));
+    }
+
+    #[test]
+    fn scrolls_to_the_selected_row() {
+        let changed = vec![true; 10];
+        let mut view = DiffView {
+            page: 3,
+            ..DiffView::default()
+        };
+        view.layout(source(0), &changed, &[]);
+
+        assert_eq!(view.window(Some(5)), 3..6);
+        assert_eq!(view.window(Some(4)), 3..6);
+        assert_eq!(view.window(Some(1)), 1..4);
+        assert_eq!(view.window(None), 1..4);
+
+        // The offset is kept while the same diff is laid out again, and reset for
+        // another one
+        view.layout(source(0), &changed, &[]);
+        assert_eq!(view.offset, 1);
+        view.layout(source(1), &changed, &[]);
+        assert_eq!(view.offset, 0);
+
+        // Past the end after the rows got fewer
+        assert_eq!(scroll(8, None, 3, 5), 2..5);
+        assert_eq!(scroll(0, Some(0), 0, 5), 0..1);
+    }
+}
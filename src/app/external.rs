@@ -0,0 +1,44 @@
+use std::io;
+use std::process::ExitStatus;
+
+use tokio::process::Command;
+
+/// A program the terminal is handed to, the checker is shown again once it exits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl ExternalCommand {
+    /// Runs a command line from the settings on the files, `None` when it is empty
+    pub fn new(command: &str, files: Vec<String>) -> Option<Self> {
+        let mut words = command.split_whitespace().map(String::from);
+        let program = words.next()?;
+        let mut args: Vec<String> = words.collect();
+        args.extend(files);
+
+        Some(Self { program, args })
+    }
+
+    pub async fn run(&self) -> io::Result<ExitStatus> {
+        Command::new(&self.program)
+            .args(&self.args)
+            .status()
+            .await
+            .map_err(|err| {
+                io::Error::new(err.kind(), format!("Cannot run {}: {}", self.program, err))
+            })
+    }
+}
+
+impl std::fmt::Display for ExternalCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use tui_logger::Drain;
+
+/// Lines kept for searching, older ones are dropped
+const CAPACITY: usize = 10000;
+
+static LINES: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+
+/// A line of the logs panel
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub time: String,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+/// Sends the records to the logs panel, keeping a copy of them that can be searched
+struct SearchableLogger;
+
+impl Log for SearchableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        Drain::new().log(record);
+
+        let mut lines = LINES.lock().unwrap_or_else(|err| err.into_inner());
+        if lines.len() == CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(LogLine {
+            time: chrono::Local::now().format("%H:%M:%S").to_string(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Logs to the logs panel, like `tui_logger::init_logger`
+pub fn init_logger(max_level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_max_level(max_level);
+    log::set_logger(&SearchableLogger)
+}
+
+/// The lines logged so far, oldest first
+pub fn lines() -> Vec<LogLine> {
+    LINES
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .iter()
+        .cloned()
+        .collect()
+}
@@ -1,47 +1,56 @@
-use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use log::{debug, error, warn};
-use serde::{Deserialize, Serialize};
-use similar::{ChangeTag, TextDiff};
-use ratatui::widgets::ListState;
-
-use self::actions::Actions;
+use ratatui::widgets::{ListState, TableState};
+use tokio::sync::broadcast::{self, error::TryRecvError};
+
+use self::actions::{Actions, Keymap};
+use self::diff_cache::{DiffCache, TestDiff};
+use self::diff_view::DiffView;
+use self::external::ExternalCommand;
+use self::mouse::Areas;
+use self::search::{Search, SearchPanel};
 use self::state::AppState;
 use crate::app::actions::Action;
 use crate::inputs::key::Key;
 use crate::io::IoEvent;
+use crate::report::diff_lines;
+use crate::runner::{Job, ProgressEvent, Runner};
+use crate::settings::Settings;
+use crate::suite::{
+    Group, History, Results, Test, TestChange, TestStatus, HISTORY_OUTPUTS_PATH, HISTORY_PATH,
+};
 
 pub mod actions;
+pub mod diff;
+pub mod diff_cache;
+pub mod diff_view;
+pub mod external;
+pub mod logs;
+pub mod mouse;
+pub mod search;
 pub mod state;
 pub mod ui;
 
-const DB_PATH: &str = "./data.json";
-const CHECKSTYLE_SCORE: isize = 10;
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct Test {
-    pub id: usize,
-    pub name: String,
-    pub status: String,
-    pub log: String,
-    pub time_normal: f64,
-    pub time_valgrind: f64,
-    pub timeout: u64,
-    pub test_score: usize,
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct Data {
-    tests: Vec<Vec<Test>>,
-    test_path: String,
-    exec_name: Vec<String>,
-    valgrind_enabled: bool,
-}
+/// Columns and rows the help and logs panels are resized by
+const PANEL_STEP: u16 = 2;
+const MIN_HELP_WIDTH: u16 = 16;
+const MAX_HELP_WIDTH: u16 = 80;
+/// The borders and a line
+const MIN_LOGS_HEIGHT: u16 = 3;
+const MAX_LOGS_HEIGHT: u16 = 40;
+/// The diff of the selected test is computed at most this often while its output is
+/// printed, the whole output being diffed again each time
+const STREAMED_DIFF_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum AppReturn {
     Exit,
     Continue,
+    /// Suspends the checker while the program runs
+    Open(ExternalCommand),
 }
 
 /// The main application, containing the state
@@ -53,155 +62,201 @@ pub struct App {
     /// State
     is_loading: bool,
     pub unwritten_data: bool,
+    /// A full run was started, the HTML report is written once it ends
+    pub pending_report: bool,
     state: AppState,
     test_num: usize,
     pub selected_tab: usize,
-    pub test_list: Vec<Vec<Test>>,
+    /// The suite, its results and the checkstyle findings, shared with `run`
+    pub runner: Runner,
     pub test_list_state: ListState,
     windows_list_state: ListState,
     pub log_list_state: ListState,
 
-    pub valgrind_enabled: bool,
-    pub test_path: String,
-    pub exec_name: Vec<String>,
+    /// Scrolls the subtotals to the group of the selected test
+    pub subtotals_state: TableState,
 
-    pub current_ref: String,
-    pub checkstyle: String,
+    /// Input of the selected test
+    pub current_input: String,
+    /// The input is shown next to the test log
+    pub show_input: bool,
+    /// First line of the input shown
+    pub input_offset: usize,
     pub vmchecker_out: String,
-    pub diff: Vec<(&'static str, String)>,
-    pub errors: Vec<i32>
+    /// Diff of the selected test, computed by the IO threads
+    pub diff: Arc<TestDiff>,
+    /// The test the diff was computed for, as `(test_index, exec_index)`
+    pub diff_test: Option<(usize, usize)>,
+    pub diff_cache: DiffCache,
+    /// The selected test printed output its diff does not show yet
+    streamed_output: bool,
+    /// When the diff of the selected test was last asked for while it printed output
+    streamed_diff: Option<Instant>,
+    /// The test log shows the ref and the output in two columns
+    pub side_by_side: bool,
+    /// The test log shows whitespace and invisible characters with visible glyphs
+    pub show_whitespace: bool,
+    /// Rows of the test log, with the unchanged lines folded
+    pub diff_view: DiffView,
+    pub search: Search,
+    /// First row of the checkstyle popup shown
+    pub checkstyle_scroll: u16,
+    /// The match of the search the checkstyle popup was scrolled to
+    pub checkstyle_match: Option<usize>,
+    /// Line selected in the logs panel while it is searched
+    pub logs_state: ListState,
+    /// Previous full runs, oldest first
+    pub history: History,
+    /// The results shown are the ones of the last run in the history, they are compared
+    /// to the run before it until a test is run again
+    pub showing_last_run: bool,
+    /// Row selected in the history view, the latest run is the first row
+    pub history_state: TableState,
+    /// Run marked to be compared with the selected one
+    pub marked_run: Option<usize>,
+    /// Indexes of the older and newer compared runs
+    pub compare: Option<(usize, usize)>,
+    pub compare_state: TableState,
+    /// Diff between the outputs of the selected test in the compared runs
+    pub compare_diff: Vec<(&'static str, String)>,
+    /// The progress of the runs, published by the IO threads on the events of the runner
+    progress: broadcast::Receiver<ProgressEvent>,
+    /// `make build` is running
+    pub building: bool,
+    pub settings: Settings,
+    keymap: Keymap,
+    pub show_help: bool,
+    pub show_logs: bool,
+    /// Columns of the help panel and rows of the logs panel, when there is room for them
+    pub help_width: u16,
+    pub logs_height: u16,
+    /// Where the panels were drawn, set when drawing
+    pub areas: Areas,
+    /// When and where the mouse was clicked last, to tell double clicks apart
+    last_click: Option<(Instant, (u16, u16))>,
 }
 
 impl App {
-    pub fn new(io_tx: tokio::sync::mpsc::Sender<IoEvent>) -> Self {
-        let actions = vec![Action::Quit].into();
+    /// The interactive checker of the loaded suite
+    pub fn new(io_tx: tokio::sync::mpsc::Sender<IoEvent>, runner: Runner) -> Self {
+        let actions = Actions::new(vec![Action::Quit], &Keymap::default());
         let is_loading = false;
         let state = AppState::default();
 
-        let db_content = fs::read_to_string(DB_PATH).unwrap();
-        let json: Data = serde_json::from_str::<Data>(&db_content).unwrap();
-        let test_list = json.tests;
-        let test_path = json.test_path;
-        let exec_name = json.exec_name;
+        let progress = runner.subscribe();
         let mut test_list_state = ListState::default();
         test_list_state.select(Some(0));
         let mut windows_list_state = ListState::default();
         windows_list_state.select(Some(0));
         let mut log_list_state = ListState::default();
         log_list_state.select(None);
-        let valgrind_enabled = json.valgrind_enabled;
         let selected_tab = 0usize;
         let unwritten_data = false;
-        let test_num = test_list.iter().map(|list| list.len()).sum();
-        let mut errors = vec![0, 0, 0];
-
-        let current_ref = fs::read_to_string(format!(
-            "{}ref/{:02}-{}.ref",
-            test_path, test_list[0][0].id, exec_name[0]
-        ))
-        .unwrap();
+        let pending_report = false;
+        let test_num = runner.suite.tests.iter().map(|list| list.len()).sum();
+        // There is no history before the first full run
+        let history = History::load(HISTORY_PATH).unwrap_or_default();
+        let suite = &runner.suite;
+        let showing_last_run = history.is_last(&suite.exec_name, &suite.tests, &suite.scoring);
+        let mut history_state = TableState::default();
+        history_state.select(Some(0));
 
-        let checkstyle = fs::read_to_string(format!("{}checkstyle.txt", test_path)).unwrap();
         let vmchecker_out = String::new();
 
-        checkstyle
-        .lines().for_each(|line| {
-            match line {
-                _ if line.contains("CHECK") => errors[0] += 1,
-                _ if line.contains("WARNING") => errors[1] += 1,
-                _ if line.contains("ERROR") => errors[2] += 1,
-                _ => (),
-            };
-        });
-
-        let diff = TextDiff::from_lines(&current_ref, &test_list[0][0].log)
-            .iter_all_changes()
-            .map(|item| {
-                let sign = match item.tag() {
-                    ChangeTag::Delete => "-",
-                    ChangeTag::Insert => "+",
-                    ChangeTag::Equal => " ",
-                };
-
-                match item.missing_newline() {
-                    true => (sign, format!("{}", item)),
-                    false => (sign, format!("{}⏎", item)),
-                }
-            })
-            .collect();
-
         Self {
             io_tx,
             actions,
             is_loading,
             unwritten_data,
+            pending_report,
             state,
             test_num,
             selected_tab,
-            test_list,
+            runner,
             test_list_state,
             windows_list_state,
             log_list_state,
-            valgrind_enabled,
-            test_path,
-            exec_name,
-            current_ref,
-            checkstyle,
+            subtotals_state: TableState::default(),
+            // Loaded with the diff of the first test
+            current_input: String::new(),
+            show_input: true,
+            input_offset: 0,
             vmchecker_out,
-            diff,
-            errors,
+            diff: Arc::default(),
+            diff_test: None,
+            diff_cache: DiffCache::default(),
+            side_by_side: false,
+            show_whitespace: false,
+            diff_view: DiffView::default(),
+            search: Search::default(),
+            checkstyle_scroll: 0,
+            checkstyle_match: None,
+            logs_state: ListState::default(),
+            history,
+            showing_last_run,
+            history_state,
+            marked_run: None,
+            compare: None,
+            compare_state: TableState::default(),
+            compare_diff: Vec::new(),
+            progress,
+            building: false,
+            settings: Settings::default(),
+            keymap: Keymap::default(),
+            show_help: true,
+            show_logs: true,
+            help_width: 32,
+            logs_height: 10,
+            areas: Areas::default(),
+            last_click: None,
+            streamed_output: false,
+            streamed_diff: None,
         }
     }
 
     /// Handle a user action
     pub async fn do_action(&mut self, key: Key) -> AppReturn {
-        if let Some(action) = self.actions.find(key) {
+        // The search prompt takes the keys typed in it
+        if self.search.editing && self.search.edit(key) {
+            return AppReturn::Continue;
+        }
+
+        if let Some(&action) = self.actions.find(key) {
             debug!("Run action [{:?}]", action);
+
+            // The history view takes the keys used to move around
+            if let Some(true) = self.state.get_history() {
+                if let Some(result) = self.history_action(action) {
+                    return result;
+                }
+            }
+
             match action {
                 Action::Quit => AppReturn::Exit,
                 Action::Run => {
                     self.dispatch(IoEvent::RunAll(self.test_num)).await;
                     AppReturn::Continue
                 }
-                Action::RunTaskOne => {
-                    let mut task_one = Vec::new();
-                    for test in self.test_list[0].iter() {
-                        task_one.push((test.id, 0));
+                Action::RunGroup(group) => {
+                    if let Some(group) = self.runner.suite.groups.get(group) {
+                        let tests = self.group_tests(group);
+                        self.dispatch(IoEvent::RunFailed(tests)).await;
                     }
-
-                    self.dispatch(IoEvent::RunFailed(task_one)).await;
                     AppReturn::Continue
                 }
-                Action::RunTaskTwo => {
-                    let mut task_two = Vec::new();
-                    for test in self.test_list[1].iter() {
-                        task_two.push((test.id, 1));
+                Action::RunSelectedGroup => {
+                    if let Some(group) = self.selected_group() {
+                        let tests = self.group_tests(&self.runner.suite.groups[group]);
+                        self.dispatch(IoEvent::RunFailed(tests)).await;
                     }
-
-                    self.dispatch(IoEvent::RunFailed(task_two)).await;
-                    AppReturn::Continue
-                }
-                Action::RunTaskThree => {
-                    let mut task_three = Vec::new();
-                    for test in self.test_list[2].iter() {
-                        task_three.push((test.id, 2));
-                    }
-
-                    self.dispatch(IoEvent::RunFailed(task_three)).await;
                     AppReturn::Continue
                 }
                 Action::RunFailed => {
                     let mut failed = Vec::new();
-                    for (index, execs) in self.test_list.iter().enumerate() {
-                        for test in execs {
-                            // TODO make this more eficient
-                            if test.status == "0"
-                                || test.status == "TIMEOUT"
-                                || test.status == "CRASHED"
-                                || test.status == "MEMLEAKS"
-                                || test.status == "ERROR"
-                            {
-                                failed.push((test.id, index));
+                    for (index, execs) in self.runner.suite.tests.iter().enumerate() {
+                        for (test_index, test) in execs.iter().enumerate() {
+                            if test.status.needs_rerun() {
+                                failed.push((test_index, index));
                             }
                         }
                     }
@@ -210,39 +265,19 @@ impl App {
                     AppReturn::Continue
                 }
                 Action::RunCurrent => {
-                    self.dispatch(IoEvent::Make).await;
-                    if let Some(index) = self.test_list_state.selected() {
-                        let (test_index, exec_index) = get_list_index(&self.test_list, index);
-
-                        self.test_list[exec_index][test_index].status.clear();
-                        self.test_list[exec_index][test_index]
-                            .status
-                            .push_str("RUNNING");
-
-                        self.dispatch(IoEvent::RunTest(test_index, exec_index))
-                            .await;
-                    } else {
-                        warn!("No test selected");
-                    }
+                    self.run_current().await;
                     AppReturn::Continue
                 }
+                // The tests, the test log and the logs panel
                 Action::RightList => {
                     if let Some(index) = self.windows_list_state.selected() {
-                        if index == 1 {
-                            self.windows_list_state.select(Some(0));
-                        } else {
-                            self.windows_list_state.select(Some(1));
-                        }
+                        self.windows_list_state.select(Some((index + 1) % 3));
                     }
                     AppReturn::Continue
                 }
                 Action::LeftList => {
                     if let Some(index) = self.windows_list_state.selected() {
-                        if index == 1 {
-                            self.windows_list_state.select(Some(0));
-                        } else {
-                            self.windows_list_state.select(Some(1));
-                        }
+                        self.windows_list_state.select(Some((index + 2) % 3));
                     }
                     AppReturn::Continue
                 }
@@ -309,12 +344,113 @@ impl App {
                     AppReturn::Continue
                 }
                 Action::ActivateValgrind => {
-                    self.valgrind_enabled = !self.valgrind_enabled;
+                    self.runner.suite.valgrind_enabled = !self.runner.suite.valgrind_enabled;
 
                     AppReturn::Continue
                 }
+                Action::ShowHistory => {
+                    self.state.update_history();
+                    AppReturn::Continue
+                }
+                // Only used by the history view
+                Action::MarkRun => AppReturn::Continue,
+                Action::ToggleDiffView => {
+                    self.side_by_side = !self.side_by_side;
+                    // Rows are numbered differently, the first difference is selected again
+                    self.diff_view.collapse();
+                    self.log_list_state.select(None);
+                    AppReturn::Continue
+                }
+                Action::ShowInput => {
+                    self.show_input = !self.show_input;
+                    AppReturn::Continue
+                }
+                Action::ShowWhitespace => {
+                    self.show_whitespace = !self.show_whitespace;
+                    AppReturn::Continue
+                }
+                Action::ShowHelp => {
+                    self.show_help = !self.show_help;
+                    AppReturn::Continue
+                }
+                Action::ShowLogs => {
+                    self.show_logs = !self.show_logs;
+                    AppReturn::Continue
+                }
+                Action::GrowHelp | Action::ShrinkHelp => {
+                    self.show_help = true;
+                    self.help_width = match action {
+                        Action::GrowHelp => self.help_width + PANEL_STEP,
+                        _ => self.help_width.saturating_sub(PANEL_STEP),
+                    }
+                    .clamp(MIN_HELP_WIDTH, MAX_HELP_WIDTH);
+                    AppReturn::Continue
+                }
+                Action::GrowLogs | Action::ShrinkLogs => {
+                    self.show_logs = true;
+                    self.logs_height = match action {
+                        Action::GrowLogs => self.logs_height + PANEL_STEP,
+                        _ => self.logs_height.saturating_sub(PANEL_STEP),
+                    }
+                    .clamp(MIN_LOGS_HEIGHT, MAX_LOGS_HEIGHT);
+                    AppReturn::Continue
+                }
+                Action::OpenDiffTool
+                | Action::EditInput
+                | Action::EditOutput
+                | Action::EditValgrind => match self.external_command(action) {
+                    Some(command) => AppReturn::Open(command),
+                    None => AppReturn::Continue,
+                },
+                Action::NextDifference
+                | Action::PreviousDifference
+                | Action::PageUp
+                | Action::PageDown
+                | Action::FirstLine
+                | Action::LastLine
+                | Action::ExpandLines => {
+                    self.log_action(action);
+                    AppReturn::Continue
+                }
+                Action::Search => {
+                    let (panel, origin) = match self.windows_list_state.selected() {
+                        _ if self.state.get_checkstyle() == Some(true) => {
+                            (SearchPanel::Checkstyle, None)
+                        }
+                        Some(2) => (SearchPanel::Logs, self.logs_state.selected()),
+                        _ => (SearchPanel::TestLog, self.log_list_state.selected()),
+                    };
+                    self.search.start(panel, origin);
+                    AppReturn::Continue
+                }
+                Action::NextMatch | Action::PreviousMatch => {
+                    let from = match self.search.panel {
+                        SearchPanel::TestLog => self.log_list_state.selected(),
+                        _ => self.search.current,
+                    };
+                    let row = if action == Action::NextMatch {
+                        self.search.next(from)
+                    } else {
+                        self.search.previous(from)
+                    };
+
+                    if let Some(row) = row {
+                        self.show_match(row);
+                    }
+                    AppReturn::Continue
+                }
+                Action::ClearSearch => {
+                    self.search.clear();
+                    AppReturn::Continue
+                }
+                Action::WriteReport => {
+                    self.dispatch(IoEvent::WriteReport).await;
+                    AppReturn::Continue
+                }
                 Action::RunCheckstyle => {
                     self.state.update_checkstyle();
+                    self.checkstyle_scroll = 0;
+                    self.checkstyle_match = None;
                     if let Some(true) = self.state.get_checkstyle() {
                         self.dispatch(IoEvent::LoadChecksyle).await;
                     }
@@ -345,14 +481,185 @@ impl App {
     pub async fn update_on_tick(&mut self) -> AppReturn {
         // here we just increment a counter
         self.state.incr_tick();
-        if self.unwritten_data && self.state.count_tick().unwrap() % 100 == 0 {
+        if self.unwritten_data && self.state.count_tick().unwrap().is_multiple_of(100) {
             let data = self.save_data();
             self.dispatch(IoEvent::SaveData(data)).await;
             self.unwritten_data = false;
         }
+        if self.follow_progress() {
+            self.streamed_output = true;
+        }
+        let diffed_recently = self
+            .streamed_diff
+            .is_some_and(|at| at.elapsed() < STREAMED_DIFF_INTERVAL);
+        if self.streamed_output && !diffed_recently {
+            self.streamed_output = false;
+            self.streamed_diff = Some(Instant::now());
+            self.dispatch(IoEvent::UpdateRef).await;
+        }
+        if self.pending_report && !self.is_running() {
+            self.pending_report = false;
+            self.dispatch(IoEvent::RecordRun).await;
+            self.dispatch(IoEvent::WriteReport).await;
+        }
         AppReturn::Continue
     }
 
+    /// Marks the test as waiting for a free IO thread and sends it to them
+    pub async fn queue_test(&mut self, test_index: usize, exec_index: usize) {
+        let test = &mut self.runner.suite.tests[exec_index][test_index];
+        test.status = TestStatus::Queued;
+
+        self.runner.events.publish(ProgressEvent::TestQueued {
+            exec: self.runner.suite.exec_name[exec_index].clone(),
+            id: test.id,
+        });
+        self.dispatch(IoEvent::RunTest(test_index, exec_index))
+            .await;
+    }
+
+    /// Follows the build and the output of the running tests as it is printed, returning
+    /// whether the diff of the selected test is outdated
+    fn follow_progress(&mut self) -> bool {
+        let mut selected_changed = false;
+        let selected = self
+            .test_list_state
+            .selected()
+            .map(|index| get_list_index(&self.runner.suite.tests, index));
+
+        loop {
+            let event = match self.progress.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Lagged(skipped)) => ProgressEvent::Lagged { skipped },
+                Err(_) => break,
+            };
+
+            match event {
+                ProgressEvent::BuildStarted => self.building = true,
+                ProgressEvent::BuildFinished { .. } => self.building = false,
+                ProgressEvent::TestStarted { exec, id, .. } => {
+                    if let Some(test) = self.running_test(&exec, id) {
+                        test.log.clear();
+                    }
+                    if let Some(position) = self.test_position(&exec, id) {
+                        self.diff_cache.invalidate(position);
+                    }
+                }
+                ProgressEvent::TestOutput { exec, id, chunk } => {
+                    let Some(position) = self.test_position(&exec, id) else {
+                        continue;
+                    };
+                    // The output of a finished test is already complete
+                    if let Some(test) = self.running_test(&exec, id) {
+                        test.log.push_str(&chunk);
+                        self.diff_cache.invalidate(position);
+                        selected_changed |= selected == Some(position);
+                    }
+                }
+                // The logs are complete again once the tests finish
+                ProgressEvent::Lagged { skipped } => {
+                    warn!("{} progress events were lost", skipped)
+                }
+                _ => {}
+            }
+        }
+
+        selected_changed
+    }
+
+    /// Builds the homework and runs the selected test
+    async fn run_current(&mut self) {
+        self.dispatch(IoEvent::Make).await;
+        if let Some(index) = self.test_list_state.selected() {
+            let (test_index, exec_index) = get_list_index(&self.runner.suite.tests, index);
+
+            self.runner.suite.tests[exec_index][test_index].status = TestStatus::Running;
+
+            self.dispatch(IoEvent::RunTest(test_index, exec_index))
+                .await;
+        } else {
+            warn!("No test selected");
+        }
+    }
+
+    /// Computes the diff of the selected test again, once its files were changed
+    /// outside of the checker
+    pub async fn refresh_diff(&mut self) {
+        if let Some(test) = self.diff_test.take() {
+            self.diff_cache.invalidate(test);
+        }
+        self.dispatch(IoEvent::UpdateRef).await;
+    }
+
+    /// The program opening the files of the selected test, `None` when they cannot be
+    /// opened
+    fn external_command(&self, action: Action) -> Option<ExternalCommand> {
+        let index = self.test_list_state.selected()?;
+        let (test_index, exec_index) = get_list_index(&self.runner.suite.tests, index);
+        let job = Job::new(
+            &self.runner.suite.test_path,
+            &self.runner.suite.exec_name[exec_index],
+            &self.runner.suite.tests[exec_index][test_index],
+            false,
+        );
+
+        let tools = &self.settings.tools;
+        let (command, files) = match action {
+            Action::OpenDiffTool => (
+                tools.diff.clone(),
+                vec![job.path("ref", "ref"), job.path("output", "out")],
+            ),
+            Action::EditInput => (tools.editor(), vec![job.path("input", "in")]),
+            Action::EditOutput => (tools.editor(), vec![job.path("output", "out")]),
+            Action::EditValgrind => (tools.editor(), vec![job.path("output", "valgrind")]),
+            _ => return None,
+        };
+
+        if let Some(missing) = files.iter().find(|file| !Path::new(file).exists()) {
+            warn!("Cannot open {}, it does not exist yet", missing);
+            return None;
+        }
+        let command = ExternalCommand::new(&command, files);
+        if command.is_none() {
+            error!("No program is set to open the files with");
+        }
+
+        command
+    }
+
+    /// Shows the diff computed for the test in the test log
+    pub fn show_diff(&mut self, test: (usize, usize), diff: Arc<TestDiff>) {
+        // The lines of another test are folded again
+        if self.diff_test != Some(test) {
+            self.diff_view.collapse();
+            self.input_offset = 0;
+        }
+        self.diff = diff;
+        self.diff_test = Some(test);
+    }
+
+    /// The `(test_index, exec_index)` of a test
+    fn test_position(&self, exec: &str, id: usize) -> Option<(usize, usize)> {
+        let exec_index = self
+            .runner
+            .suite
+            .exec_name
+            .iter()
+            .position(|name| name == exec)?;
+        let test_index = self.runner.suite.tests[exec_index]
+            .iter()
+            .position(|test| test.id == id)?;
+
+        Some((test_index, exec_index))
+    }
+
+    fn running_test(&mut self, exec: &str, id: usize) -> Option<&mut Test> {
+        let (test_index, exec_index) = self.test_position(exec, id)?;
+        let test = &mut self.runner.suite.tests[exec_index][test_index];
+
+        (test.status == TestStatus::Running).then_some(test)
+    }
+
     /// Send a network event to the IO thread
     pub async fn dispatch(&mut self, action: IoEvent) {
         // `is_loading` will be set to false again after the async action has finished in io/handler.rs
@@ -374,9 +681,23 @@ impl App {
         self.is_loading
     }
 
+    /// Uses the preferences of the user, with the default keys when theirs cannot be used
+    pub fn apply_settings(&mut self, settings: Settings) {
+        match Keymap::new(&settings.keys, self.runner.suite.groups.len()) {
+            Ok(keymap) => self.keymap = keymap,
+            Err(problems) => {
+                self.runner.suite.warnings.extend(problems);
+                self.runner.suite.warnings.push(String::from(
+                    "The [keys] of the settings are ignored, using the default keys",
+                ));
+            }
+        }
+        self.settings = settings;
+    }
+
     pub fn initialized(&mut self) {
         // Update contextual actions
-        self.actions = vec![
+        let mut actions = vec![
             Action::Quit,
             Action::Run,
             Action::RunFailed,
@@ -387,59 +708,249 @@ impl App {
             Action::DownList,
             Action::ActivateValgrind,
             Action::RunCheckstyle,
-            Action::RunTaskOne,
-            Action::RunTaskTwo,
-            Action::RunTaskThree,
+            Action::RunSelectedGroup,
+            Action::WriteReport,
+            Action::ShowHistory,
+            Action::MarkRun,
+            Action::ToggleDiffView,
+            Action::NextDifference,
+            Action::PreviousDifference,
+            Action::PageUp,
+            Action::PageDown,
+            Action::FirstLine,
+            Action::LastLine,
+            Action::ExpandLines,
+            Action::Search,
+            Action::NextMatch,
+            Action::PreviousMatch,
+            Action::ClearSearch,
+            Action::ShowInput,
+            Action::ShowWhitespace,
+            Action::OpenDiffTool,
+            Action::EditInput,
+            Action::EditOutput,
+            Action::EditValgrind,
+            Action::ShowHelp,
+            Action::ShowLogs,
+            Action::GrowHelp,
+            Action::ShrinkHelp,
+            Action::GrowLogs,
+            Action::ShrinkLogs,
             // Action::SendVMChecker,
             // Action::OpenVMChecker,
-        ]
-        .into();
-        self.state = AppState::initialized()
+        ];
+        actions.extend((0..self.runner.suite.groups.len()).map(Action::RunGroup));
+        self.actions = Actions::new(actions, &self.keymap);
+        self.state = AppState::initialized();
+
+        for warning in self.runner.suite.warnings.iter() {
+            warn!("{}", warning);
+        }
     }
 
     pub fn loaded(&mut self) {
         self.is_loading = false;
     }
 
-    pub fn calculate_score(&self) -> isize {
-        let mut score = 0isize;
-        for execs in self.test_list.iter() {
-            for test in execs {
-                score += test.status.parse::<isize>().unwrap_or(0);
+    /// Index of the smallest group containing the selected test
+    pub fn selected_group(&self) -> Option<usize> {
+        let index = self.test_list_state.selected().unwrap_or(0);
+        let (test_index, exec_index) = get_list_index(&self.runner.suite.tests, index);
+        let id = self.runner.suite.tests.get(exec_index)?.get(test_index)?.id;
+
+        // The innermost group, since children come after their parents
+        let exec = &self.runner.suite.exec_name[exec_index];
+        self.runner
+            .suite
+            .groups
+            .iter()
+            .rposition(|group| group.exec == *exec && group.ids.contains(&id))
+    }
+
+    /// The `(test_index, exec_index)` of every test in the group
+    pub fn group_tests(&self, group: &Group) -> Vec<(usize, usize)> {
+        match self
+            .runner
+            .suite
+            .exec_name
+            .iter()
+            .position(|name| *name == group.exec)
+        {
+            Some(exec_index) => group
+                .positions(&self.runner.suite.tests[exec_index])
+                .into_iter()
+                .map(|test_index| (test_index, exec_index))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// True while a test waits to be run or is running
+    pub fn is_running(&self) -> bool {
+        self.runner
+            .suite
+            .tests
+            .iter()
+            .flatten()
+            .any(|test| test.status.is_active())
+    }
+
+    /// Index in `history.runs` of the run selected in the history view
+    pub fn selected_run(&self) -> Option<usize> {
+        let row = self.history_state.selected()?;
+        self.history.runs.len().checked_sub(row + 1)
+    }
+
+    /// The tests of the two compared runs, in the order they are shown
+    pub fn compared_tests(&self) -> Vec<TestChange<'_>> {
+        match self.compare {
+            Some((before, after)) => self.history.compare(before, after),
+            None => Vec::new(),
+        }
+    }
+
+    /// Scrolls the searched panel to the match on this row
+    pub fn show_match(&mut self, row: usize) {
+        match self.search.panel {
+            SearchPanel::TestLog => {
+                self.windows_list_state.select(Some(1));
+                self.log_list_state.select(Some(row));
+            }
+            SearchPanel::Logs => self.logs_state.select(Some(row)),
+            // Scrolled when drawn, since lines are wrapped
+            SearchPanel::Checkstyle => {}
+        }
+    }
+
+    /// Moves around the test log. Jumping to a difference also moves the focus to it.
+    fn log_action(&mut self, action: Action) {
+        let size = self.state.get_diffsize().unwrap_or(0);
+        let selected = self.log_list_state.selected();
+        let view = &mut self.diff_view;
+
+        let row = match (action, selected) {
+            (Action::NextDifference, Some(selected)) => view.next_hunk(selected),
+            (Action::NextDifference, None) => view.hunks.first().copied(),
+            (Action::PreviousDifference, Some(selected)) => view.previous_hunk(selected),
+            (Action::PreviousDifference, None) => view.hunks.last().copied(),
+            // The other keys only work once the test log is focused
+            (_, None) => return,
+            (Action::PageUp, Some(selected)) => Some(selected.saturating_sub(view.page.max(1))),
+            (Action::PageDown, Some(selected)) => {
+                Some((selected + view.page.max(1)).min(size.saturating_sub(1)))
+            }
+            (Action::FirstLine, _) => Some(0),
+            (Action::LastLine, _) => Some(size.saturating_sub(1)),
+            (Action::ExpandLines, Some(selected)) => {
+                if !view.expand(selected) {
+                    view.collapse();
+                }
+                None
             }
+            _ => None,
+        };
+
+        if let Some(row) = row {
+            self.windows_list_state.select(Some(1));
+            self.log_list_state.select(Some(row));
         }
+    }
 
-        if self.errors.iter().sum::<i32>() == 0 {
-            score += CHECKSTYLE_SCORE;
+    /// Handles the keys of the history view, returning `None` for the ones it
+    /// does not use
+    fn history_action(&mut self, action: Action) -> Option<AppReturn> {
+        let rows = match self.compare {
+            Some(_) => self.compared_tests().len(),
+            None => self.history.runs.len(),
+        };
+        let state = match self.compare {
+            Some(_) => &mut self.compare_state,
+            None => &mut self.history_state,
+        };
+
+        match action {
+            Action::UpList if rows > 0 => {
+                let selected = state.selected().unwrap_or(0);
+                state.select(Some(if selected > 0 { selected - 1 } else { rows - 1 }));
+            }
+            Action::DownList if rows > 0 => {
+                let selected = state.selected().unwrap_or(0);
+                state.select(Some(if selected + 1 >= rows {
+                    0
+                } else {
+                    selected + 1
+                }));
+            }
+            Action::UpList | Action::DownList => {}
+            Action::MarkRun => match (self.compare, self.marked_run, self.selected_run()) {
+                // Back to the list of runs
+                (Some(_), _, _) => {
+                    self.compare = None;
+                    self.marked_run = None;
+                }
+                (None, Some(marked), Some(selected)) if marked != selected => {
+                    self.compare = Some((marked.min(selected), marked.max(selected)));
+                    self.compare_state.select(Some(0));
+                }
+                (None, Some(_), _) => self.marked_run = None,
+                (None, None, selected) => self.marked_run = selected,
+            },
+            _ => return None,
         }
 
-        score
+        self.update_compare_diff();
+        Some(AppReturn::Continue)
+    }
+
+    /// Reads the two outputs of the test selected in the comparison
+    fn update_compare_diff(&mut self) {
+        let tests = self.compared_tests();
+        let selected = self.compare_state.selected().unwrap_or(0);
+
+        let diff = tests.get(selected).map(|change| {
+            let before = change
+                .before
+                .and_then(|test| test.read_output(HISTORY_OUTPUTS_PATH))
+                .unwrap_or_default();
+            let after = change
+                .after
+                .and_then(|test| test.read_output(HISTORY_OUTPUTS_PATH))
+                .unwrap_or_default();
+
+            diff_lines(&before, &after)
+        });
+
+        self.compare_diff = diff.unwrap_or_default();
     }
 
-    pub fn save_data(&mut self) -> Data {
-        Data {
-            tests: self.test_list.to_vec(),
-            test_path: self.test_path.clone(),
-            exec_name: self.exec_name.clone(),
-            valgrind_enabled: self.valgrind_enabled,
+    /// The test got fewer points than in the previous run
+    pub fn is_regression(&self, exec_index: usize, test: &Test) -> bool {
+        if test.status.is_active() {
+            return false;
         }
+
+        self.history
+            .previous(self.showing_last_run)
+            .and_then(|run| run.test(&self.runner.suite.exec_name[exec_index], test.id))
+            .is_some_and(|previous| self.runner.suite.scoring.test_points(test) < previous.score)
+    }
+
+    pub fn save_data(&mut self) -> Results {
+        self.runner.results()
     }
 }
 
-pub fn get_list_index(lists: &Vec<Vec<Test>>, index: usize) -> (usize, usize) {
+pub fn get_list_index(lists: &[Vec<Test>], index: usize) -> (usize, usize) {
     let mut cumulative_index = 0;
-    let mut list_index = 0;
 
-    for list in lists {
+    for (list_index, list) in lists.iter().enumerate() {
         cumulative_index += list.len();
 
         if cumulative_index > index {
             let index_in_list = index - (cumulative_index - list.len());
             return (index_in_list, list_index);
         }
-
-        list_index += 1;
     }
 
-    return (0, 0);
+    (0, 0)
 }
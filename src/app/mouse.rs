@@ -0,0 +1,129 @@
+use std::time::{Duration, Instant};
+
+use ratatui::layout::Rect;
+
+use super::{App, AppReturn};
+use crate::app::actions::Action;
+use crate::inputs::key::Key;
+use crate::inputs::mouse::{Mouse, MouseKind};
+use crate::io::IoEvent;
+
+/// Two clicks on the same cell within this time run the clicked test
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// Rows of the test log and of the checkstyle popup moved by a step of the wheel
+const SCROLL_ROWS: usize = 3;
+
+/// Where the panels were drawn last, to find what the mouse points at
+#[derive(Debug, Clone, Default)]
+pub struct Areas {
+    pub tests: Rect,
+    pub test_log: Rect,
+    pub logs: Rect,
+    pub help: Rect,
+    /// The key of each row of the help panel
+    pub help_keys: Vec<Key>,
+    /// Rows the checkstyle popup can be scrolled by
+    pub checkstyle_rows: u16,
+}
+
+/// The row of the area's content at the cell, `None` outside of it or on its borders
+fn row_in(area: Rect, column: u16, row: u16) -> Option<usize> {
+    let inside = column > area.x
+        && column + 1 < area.x + area.width
+        && row > area.y
+        && row + 1 < area.y + area.height;
+
+    inside.then(|| (row - area.y - 1) as usize)
+}
+
+impl App {
+    /// Handle a click or a step of the scroll wheel
+    pub async fn do_mouse(&mut self, mouse: Mouse) -> AppReturn {
+        let Mouse { kind, column, row } = mouse;
+
+        let double_click = match (kind, self.last_click) {
+            (MouseKind::Click, Some((time, last))) => {
+                last == (column, row) && time.elapsed() < DOUBLE_CLICK
+            }
+            _ => false,
+        };
+        if kind == MouseKind::Click {
+            // A third click starts over
+            self.last_click = (!double_click).then(|| (Instant::now(), (column, row)));
+        }
+
+        // The pop-ups take the wheel, and are closed with their keys
+        if let Some(true) = self.state.get_checkstyle() {
+            let scroll = self.checkstyle_scroll as usize;
+            self.checkstyle_scroll = match kind {
+                MouseKind::ScrollUp => scroll.saturating_sub(SCROLL_ROWS),
+                MouseKind::ScrollDown => scroll + SCROLL_ROWS,
+                MouseKind::Click => scroll,
+            }
+            .min(self.areas.checkstyle_rows as usize) as u16;
+            return AppReturn::Continue;
+        }
+        if let Some(true) = self.state.get_history() {
+            if let Some(action) = match kind {
+                MouseKind::ScrollUp => Some(Action::UpList),
+                MouseKind::ScrollDown => Some(Action::DownList),
+                MouseKind::Click => None,
+            } {
+                if let Some(result) = self.history_action(action) {
+                    return result;
+                }
+            }
+            return AppReturn::Continue;
+        }
+
+        if let Some(index) = row_in(self.areas.tests, column, row) {
+            self.windows_list_state.select(Some(0));
+            let selected = self.test_list_state.selected().unwrap_or(0);
+            let index = match kind {
+                MouseKind::Click => self.test_list_state.offset() + index,
+                MouseKind::ScrollUp => selected.saturating_sub(1),
+                MouseKind::ScrollDown => selected + 1,
+            };
+            if index >= self.test_num {
+                return AppReturn::Continue;
+            }
+
+            if Some(index) != self.test_list_state.selected() {
+                self.test_list_state.select(Some(index));
+                self.dispatch(IoEvent::UpdateRef).await;
+            }
+            if double_click {
+                self.run_current().await;
+            }
+        } else if let Some(index) = row_in(self.areas.test_log, column, row) {
+            self.windows_list_state.select(Some(1));
+            let size = self.diff_view.rows.len();
+            let selected = self
+                .log_list_state
+                .selected()
+                .unwrap_or(self.diff_view.offset);
+            let index = match kind {
+                MouseKind::Click => self.diff_view.offset + index,
+                MouseKind::ScrollUp => selected.saturating_sub(SCROLL_ROWS),
+                MouseKind::ScrollDown => (selected + SCROLL_ROWS).min(size.saturating_sub(1)),
+            };
+
+            if index < size {
+                self.log_list_state.select(Some(index));
+            }
+        } else if row_in(self.areas.logs, column, row).is_some() {
+            if kind == MouseKind::Click {
+                self.windows_list_state.select(Some(2));
+            }
+        } else if let Some(index) = row_in(self.areas.help, column, row) {
+            // The same as pressing the key shown on the row
+            if kind == MouseKind::Click && !self.search.editing {
+                if let Some(&key) = self.areas.help_keys.get(index) {
+                    return self.do_action(key).await;
+                }
+            }
+        }
+
+        AppReturn::Continue
+    }
+}
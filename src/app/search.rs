@@ -0,0 +1,209 @@
+use regex::{escape, Regex, RegexBuilder};
+
+use crate::inputs::key::Key;
+
+/// The panel searched with `/`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchPanel {
+    #[default]
+    TestLog,
+    Checkstyle,
+    Logs,
+}
+
+/// The text searched in a panel, and the matches found in it
+#[derive(Debug, Clone, Default)]
+pub struct Search {
+    pub panel: SearchPanel,
+    pub query: String,
+    /// The prompt is open, the keys typed go to the query
+    pub editing: bool,
+    pub case_sensitive: bool,
+    /// The query is a regular expression instead of plain text
+    pub regex: bool,
+    /// Rows of the panel with a match, set when it is drawn
+    pub rows: Vec<usize>,
+    /// Row of the match jumped to
+    pub current: Option<usize>,
+    /// Row selected when the search started, matches are looked for from it
+    origin: Option<usize>,
+    /// The query changed, the panel jumps to its first match once drawn
+    pending_jump: bool,
+}
+
+impl Search {
+    /// Opens the prompt for a new search in the panel
+    pub fn start(&mut self, panel: SearchPanel, origin: Option<usize>) {
+        self.panel = panel;
+        self.query.clear();
+        self.editing = true;
+        self.rows.clear();
+        self.current = None;
+        self.origin = origin;
+    }
+
+    /// Handles a key typed in the prompt, returning false if it is not used by it
+    pub fn edit(&mut self, key: Key) -> bool {
+        match key {
+            Key::Enter => self.editing = false,
+            Key::Esc => self.clear(),
+            Key::Backspace => {
+                self.query.pop();
+            }
+            Key::Alt('c') => self.case_sensitive = !self.case_sensitive,
+            Key::Alt('r') => self.regex = !self.regex,
+            Key::Char(c) => self.query.push(c),
+            _ => return false,
+        }
+
+        self.pending_jump = self.editing;
+        true
+    }
+
+    /// Closes the prompt and forgets the matches
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.editing = false;
+        self.rows.clear();
+        self.current = None;
+    }
+
+    /// Matches are shown in the panel
+    pub fn is_active_in(&self, panel: SearchPanel) -> bool {
+        self.panel == panel && !self.query.is_empty()
+    }
+
+    /// The query, or why it is not a valid regular expression
+    pub fn pattern(&self) -> Option<Result<Regex, regex::Error>> {
+        if self.query.is_empty() {
+            return None;
+        }
+
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            escape(&self.query)
+        };
+
+        Some(
+            RegexBuilder::new(&pattern)
+                .case_insensitive(!self.case_sensitive)
+                .build(),
+        )
+    }
+
+    /// Jumps to the next match after `row`, or back to the first one
+    pub fn next(&mut self, row: Option<usize>) -> Option<usize> {
+        let next = match row {
+            Some(row) => self.rows.iter().copied().find(|&match_row| match_row > row),
+            None => None,
+        };
+        self.current = next.or_else(|| self.rows.first().copied());
+
+        self.current
+    }
+
+    /// Jumps to the previous match before `row`, or to the last one
+    pub fn previous(&mut self, row: Option<usize>) -> Option<usize> {
+        let previous = match row {
+            Some(row) => self
+                .rows
+                .iter()
+                .copied()
+                .rev()
+                .find(|&match_row| match_row < row),
+            None => None,
+        };
+        self.current = previous.or_else(|| self.rows.last().copied());
+
+        self.current
+    }
+
+    /// The row to jump to after the query changed
+    pub fn take_jump(&mut self) -> Option<usize> {
+        if !std::mem::take(&mut self.pending_jump) {
+            return None;
+        }
+
+        self.next(self.origin.and_then(|origin| origin.checked_sub(1)))
+    }
+
+    /// The number, from 1, of the match jumped to
+    pub fn current_match(&self) -> Option<usize> {
+        let current = self.current?;
+        self.rows
+            .iter()
+            .position(|&row| row == current)
+            .map(|index| index + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(query: &str, regex: bool) -> Search {
+        let mut search = Search::default();
+        search.start(SearchPanel::TestLog, None);
+        if regex {
+            search.edit(Key::Alt('r'));
+        }
+        for c in query.chars() {
+            search.edit(Key::Char(c));
+        }
+        search.edit(Key::Enter);
+
+        search
+    }
+
+    #[test]
+    fn plain_queries_match_their_text() {
+        let search = typed("a.B(", false);
+        let pattern = search.pattern().unwrap().unwrap();
+
+        assert!(pattern.is_match("x a.b( y"));
+        assert!(!pattern.is_match("axb("));
+        assert!(!search.editing);
+        assert!(search.is_active_in(SearchPanel::TestLog));
+        assert!(!search.is_active_in(SearchPanel::Logs));
+    }
+
+    #[test]
+    fn regex_queries_are_checked() {
+        let mut search = typed("a.b", true);
+        assert!(search.pattern().unwrap().unwrap().is_match("AXB"));
+
+        search.case_sensitive = true;
+        assert!(!search.pattern().unwrap().unwrap().is_match("AXB"));
+
+        search.query.push('(');
+        assert!(search.pattern().unwrap().is_err());
+
+        search.edit(Key::Esc);
+        assert!(search.pattern().is_none());
+    }
+
+    #[test]
+    fn jumps_between_matches() {
+        let mut search = typed("x", false);
+        search.rows = vec![2, 5, 9];
+
+        assert_eq!(search.next(Some(2)), Some(5));
+        assert_eq!(search.current_match(), Some(2));
+        assert_eq!(search.next(Some(9)), Some(2));
+        assert_eq!(search.previous(Some(2)), Some(9));
+        assert_eq!(search.previous(None), Some(9));
+        assert_eq!(search.current_match(), Some(3));
+    }
+
+    #[test]
+    fn jumps_from_where_the_search_started() {
+        let mut search = Search::default();
+        search.start(SearchPanel::Checkstyle, Some(5));
+        search.edit(Key::Char('x'));
+        search.rows = vec![2, 5, 9];
+
+        assert_eq!(search.take_jump(), Some(5));
+        assert_eq!(search.take_jump(), None);
+    }
+}
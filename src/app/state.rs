@@ -1,13 +1,15 @@
 use std::time::Duration;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub enum AppState {
+    #[default]
     Init,
     Initialized {
         duration: Duration,
         counter_tick: u64,
         checkstyle: bool,
         vmcheck_output: bool,
+        history: bool,
         diff_size: usize,
     },
 }
@@ -18,12 +20,14 @@ impl AppState {
         let counter_tick = 0;
         let checkstyle = false;
         let vmcheck_output = false;
+        let history = false;
         let diff_size = 0;
         Self::Initialized {
             duration,
             counter_tick,
             checkstyle,
             vmcheck_output,
+            history,
             diff_size,
         }
     }
@@ -58,6 +62,20 @@ impl AppState {
         }
     }
 
+    pub fn update_history(&mut self) {
+        if let Self::Initialized { history, .. } = self {
+            *history = !*history;
+        }
+    }
+
+    pub fn get_history(&self) -> Option<bool> {
+        if let Self::Initialized { history, .. } = self {
+            Some(*history)
+        } else {
+            None
+        }
+    }
+
     pub fn get_checkstyle(&self) -> Option<bool> {
         if let Self::Initialized { checkstyle, .. } = self {
             Some(*checkstyle)
@@ -88,9 +106,3 @@ impl AppState {
         }
     }
 }
-
-impl Default for AppState {
-    fn default() -> Self {
-        Self::Init
-    }
-}
@@ -1,48 +1,118 @@
 use std::cmp;
+use std::ops::Range;
+use std::sync::Arc;
+
+use log::Level;
 
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{
-    Block, BorderType, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table, Wrap,
+    Block, BorderType, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Sparkline,
+    Table, Wrap,
 };
 use ratatui::Frame;
+use regex::Regex;
 use tui_logger::{TuiLoggerLevelOutput, TuiLoggerWidget};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use super::actions::Actions;
+use super::actions::{Action, Actions};
+use super::diff::{inline_diff, whitespace_glyph, InlineChanges};
+use super::diff_cache::TestDiff;
+use super::diff_view::{scroll, DiffEntry, LayoutSource};
 use super::get_list_index;
+use super::logs;
+use super::mouse::Areas;
+use super::search::{Search, SearchPanel};
 use crate::app::App;
-
-pub fn draw(rect: &mut Frame, app: &mut App)
-{
+use crate::inputs::key::Key;
+use crate::suite::{format_points, Group, HistoryTest, ScoreBreakdown, TestStatus};
+
+/// Smallest terminal the checker is drawn in
+const MIN_WIDTH: u16 = 52;
+const MIN_HEIGHT: u16 = 24;
+/// Room kept for the tests and the test log before showing the help and the logs
+const MIN_BODY_WIDTH: u16 = 52;
+const MIN_BODY_HEIGHT: u16 = 14;
+/// Narrower panels are hidden
+const MIN_HELP_WIDTH: u16 = 16;
+const MIN_LOGS_HEIGHT: u16 = 3;
+const MIN_TESTS_WIDTH: u16 = 25;
+/// Rows of the subtotals shown at once
+const MAX_SUBTOTAL_ROWS: usize = 10;
+
+pub fn draw(rect: &mut Frame, app: &mut App) {
     let size = rect.size();
-    check_size(&size);
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        draw_too_small(rect, size);
+        app.areas = Areas::default();
+        return;
+    }
+
+    // The logs and the help are hidden when there is no room left for them
+    let logs_height = match app.show_logs {
+        true => app.logs_height.min(size.height - MIN_BODY_HEIGHT),
+        false => 0,
+    };
+    let help_width = match app.show_help {
+        true => app.help_width.min(size.width - MIN_BODY_WIDTH),
+        false => 0,
+    };
 
     // Vertical layout
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(10), Constraint::Length(10)].as_ref())
+        .constraints([Constraint::Min(10), Constraint::Length(logs_height)].as_ref())
         .split(size);
 
     // Body & Help
     let body_chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Min(10), Constraint::Length(32)].as_ref())
+        .constraints([Constraint::Min(10), Constraint::Length(help_width)].as_ref())
         .split(chunks[0]);
 
     // let body = draw_body(app.is_loading(), app.state());
     // rect.render_widget(body, body_chunks[0]);
 
+    let tests_width = test_list_width(app, body_chunks[0].width);
     let test_layout = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(25), Constraint::Min(20)].as_ref())
+        .constraints([Constraint::Length(tests_width), Constraint::Min(20)].as_ref())
         .split(body_chunks[0]);
 
-    let (test_list, test_info, test_log, first_diff) = draw_test_list(app);
+    let info_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4), Constraint::Min(20)].as_ref())
+        .split(test_layout[1]);
 
+    // Input & Test log
+    let (input_area, log_area) = if app.show_input {
+        let input_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(30), Constraint::Min(20)].as_ref())
+            .split(info_layout[1]);
+        (Some(input_layout[0]), input_layout[1])
+    } else {
+        (None, info_layout[1])
+    };
+
+    app.diff_view.page = log_area.height.saturating_sub(2) as usize;
+    let (test_list, test_info, test_log, mut log_state) =
+        draw_test_list(app, tests_width.saturating_sub(2), log_area.width);
+
+    let breakdown = app.runner.score_breakdown();
+    // The other groups are scrolled to
+    let subtotal_height = cmp::min(breakdown.rows().count(), MAX_SUBTOTAL_ROWS) as u16 + 2;
     let test_and_score_layout = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(10), Constraint::Length(3)].as_ref())
+        .constraints(
+            [
+                Constraint::Min(10),
+                Constraint::Length(subtotal_height),
+                Constraint::Length(3),
+            ]
+            .as_ref(),
+        )
         .split(test_layout[0]);
 
     rect.render_stateful_widget(
@@ -51,52 +121,154 @@ pub fn draw(rect: &mut Frame, app: &mut App)
         &mut app.test_list_state,
     );
 
-    let score = draw_final_score(app);
-    rect.render_widget(score, test_and_score_layout[1]);
+    let subtotals = draw_group_scores(&breakdown);
+    app.subtotals_state.select(app.selected_group());
+    rect.render_stateful_widget(
+        subtotals,
+        test_and_score_layout[1],
+        &mut app.subtotals_state,
+    );
+
+    let score = draw_final_score(&breakdown);
+    rect.render_widget(score, test_and_score_layout[2]);
 
-    let info_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(4), Constraint::Min(20)].as_ref())
-        .split(test_layout[1]);
     rect.render_widget(test_info, info_layout[0]);
+    rect.render_stateful_widget(test_log, log_area, &mut log_state);
 
-    if let Some(index) = app.windows_list_state.selected() {
-        if index == 1 {
-            if app.log_list_state.selected().is_none() {
-                app.log_list_state.select(Some(first_diff));
-            }
-            rect.render_stateful_widget(test_log, info_layout[1], &mut app.log_list_state);
-        } else {
-            rect.render_widget(test_log, info_layout[1]);
-            app.log_list_state.select(None);
-        }
+    if let Some(area) = input_area {
+        let (input, mut state) = draw_input(app, area.height.saturating_sub(2) as usize);
+        rect.render_stateful_widget(input, area, &mut state);
     }
 
-    let help = draw_help(app.actions());
-    rect.render_widget(help, body_chunks[1]);
+    app.areas.tests = test_and_score_layout[0];
+    app.areas.test_log = log_area;
+    app.areas.help = Rect::default();
+    app.areas.help_keys.clear();
+    app.areas.logs = Rect::default();
+
+    if help_width >= MIN_HELP_WIDTH {
+        let (help, help_keys) = draw_help(app.actions(), &app.runner.suite.groups);
+        rect.render_widget(help, body_chunks[1]);
+
+        app.areas.help = body_chunks[1];
+        app.areas.help_keys = help_keys;
+    }
 
     // Logs
-    let logs = draw_logs();
-    rect.render_widget(logs, chunks[1]);
+    if logs_height >= MIN_LOGS_HEIGHT {
+        let logs_focused = app.windows_list_state.selected() == Some(2);
+        match search_pattern(&app.search, SearchPanel::Logs) {
+            Some(pattern) => {
+                let logs = draw_searched_logs(app, &pattern, logs_focused);
+                rect.render_stateful_widget(logs, chunks[1], &mut app.logs_state);
+            }
+            None => rect.render_widget(draw_logs(logs_focused), chunks[1]),
+        }
+
+        app.areas.logs = chunks[1];
+    }
 
     if let Some(true) = app.state().get_checkstyle() {
+        scroll_checkstyle(app, popup_area(size, 90, 90));
         let (area, block) = draw_popup_cs(app, size, 90, 90);
 
         rect.render_widget(Clear, area);
         rect.render_widget(block, area);
     }
 
+    if let Some(true) = app.state().get_history() {
+        draw_popup_history(rect, app, popup_area(size, 90, 90));
+    }
+
     if let Some(true) = app.state().get_vmcheck() {
         let (area, block) = draw_popup_vmcheck(app, size, 90, 90);
 
         rect.render_widget(Clear, area);
         rect.render_widget(block, area);
     }
+
+    if app.search.editing || !app.search.query.is_empty() {
+        draw_search_prompt(rect, app, size);
+    }
+}
+
+/// Finds the matches of the search in the checkstyle popup, scrolling to the one
+/// jumped to
+fn scroll_checkstyle(app: &mut App, area: Rect) {
+    // Wrapped lines take more than one row
+    let width = cmp::max(area.width.saturating_sub(2), 1) as usize;
+    let wrapped = |lines: usize| -> u16 {
+        let rows: usize = app
+            .runner
+            .checkstyle
+            .output
+            .lines()
+            .take(lines)
+            .map(|line| cmp::max(line.width().div_ceil(width), 1))
+            .sum();
+        rows.min(u16::MAX as usize) as u16
+    };
+    // With the lines added after the output, see draw_popup_cs
+    let rows = wrapped(usize::MAX).saturating_add(2);
+    app.areas.checkstyle_rows = rows.saturating_sub(area.height.saturating_sub(2));
+
+    let Some(pattern) = search_pattern(&app.search, SearchPanel::Checkstyle) else {
+        return;
+    };
+
+    app.search.rows = app
+        .runner
+        .checkstyle
+        .output
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| pattern.is_match(line))
+        .map(|(row, _)| row)
+        .collect();
+    app.search.take_jump();
+
+    // The wheel scrolls away from the match until another one is jumped to
+    if app.search.current != app.checkstyle_match {
+        app.checkstyle_match = app.search.current;
+        if let Some(current) = app.search.current {
+            app.checkstyle_scroll = wrapped(current);
+        }
+    }
+}
+
+/// A rectangle in the middle of `size`, taking `x` and `y` percent of it
+fn popup_area(size: Rect, x: u16, y: u16) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - y) / 2),
+                Constraint::Percentage(y),
+                Constraint::Percentage((100 - y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(size);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - x) / 2),
+                Constraint::Percentage(x),
+                Constraint::Percentage((100 - x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(popup_layout[1])[1]
 }
 
 fn draw_popup_cs<'a>(app: &'a App, size: Rect, x: u16, y: u16) -> (Rect, Paragraph<'a>) {
+    let pattern = search_pattern(&app.search, SearchPanel::Checkstyle);
     let mut items: Vec<_> = app
+        .runner
         .checkstyle
+        .output
         .lines()
         .map(|line| {
             let style = match line {
@@ -108,15 +280,18 @@ fn draw_popup_cs<'a>(app: &'a App, size: Rect, x: u16, y: u16) -> (Rect, Paragra
 
             let split: Vec<&'a str> = line.split(':').collect();
             if split.len() < 5 {
-                return Line::from(vec![Span::raw(line)]);
+                return Line::from(highlight(vec![Span::raw(line)], pattern.as_ref()));
             }
 
-            Line::from(vec![
-                Span::styled(format!("{}:{}:{}:", split[0], split[1], split[2]), style),
-                Span::styled(split[3], Style::default().fg(Color::Blue)),
-                Span::raw(":"),
-                Span::raw(split[4]),
-            ])
+            Line::from(highlight(
+                vec![
+                    Span::styled(format!("{}:{}:{}:", split[0], split[1], split[2]), style),
+                    Span::styled(split[3], Style::default().fg(Color::Blue)),
+                    Span::raw(":"),
+                    Span::raw(split[4]),
+                ],
+                pattern.as_ref(),
+            ))
         })
         .collect();
 
@@ -124,39 +299,16 @@ fn draw_popup_cs<'a>(app: &'a App, size: Rect, x: u16, y: u16) -> (Rect, Paragra
         items.push(Line::from(vec![Span::raw("Running checkstyle")]));
     }
 
-    if app.errors.iter().sum::<i32>() == 0i32 {
+    if app.runner.checkstyle.problems() == 0 {
         items.push(Line::from(vec![Span::raw("No errors found")]));
     }
 
     let list = Paragraph::new(items)
         .block(Block::default().borders(Borders::ALL).title("Checkstyle"))
-        .wrap(Wrap { trim: true });
+        .wrap(Wrap { trim: true })
+        .scroll((app.checkstyle_scroll, 0));
 
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Percentage((100 - y) / 2),
-                Constraint::Percentage(y),
-                Constraint::Percentage((100 - y) / 2),
-            ]
-            .as_ref(),
-        )
-        .split(size);
-
-    let area = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage((100 - x) / 2),
-                Constraint::Percentage(x),
-                Constraint::Percentage((100 - x) / 2),
-            ]
-            .as_ref(),
-        )
-        .split(popup_layout[1])[1];
-
-    (area, list)
+    (popup_area(size, x, y), list)
 }
 
 fn draw_popup_vmcheck<'a>(app: &'a App, size: Rect, x: u16, y: u16) -> (Rect, Paragraph<'a>) {
@@ -189,60 +341,418 @@ fn draw_popup_vmcheck<'a>(app: &'a App, size: Rect, x: u16, y: u16) -> (Rect, Pa
         )
         .wrap(Wrap { trim: true });
 
-    let popup_layout = Layout::default()
+    (popup_area(size, x, y), list)
+}
+
+/// Tests whose status, time or output changed between two runs, with the diff of
+/// the outputs of the selected one
+fn draw_run_comparison(rect: &mut Frame, app: &mut App, area: Rect, before: usize, after: usize) {
+    let describe = |index: usize| {
+        let run = &app.history.runs[index];
+        format!(
+            "{} ({}, {}/{})",
+            run.date(),
+            run.commit.as_deref().unwrap_or("no commit"),
+            format_points(run.score),
+            format_points(run.max_score)
+        )
+    };
+    let block = Block::default().borders(Borders::ALL).title(format!(
+        "Comparing {} with {}, press <m> to go back",
+        describe(before),
+        describe(after)
+    ));
+    let inner = block.inner(area);
+    rect.render_widget(block, area);
+
+    let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Percentage((100 - y) / 2),
-                Constraint::Percentage(y),
-                Constraint::Percentage((100 - y) / 2),
+                Constraint::Length(1),
+                Constraint::Percentage(50),
+                Constraint::Min(4),
             ]
             .as_ref(),
         )
-        .split(size);
+        .split(inner);
+
+    let changes = app.compared_tests();
+    let status_changes = changes
+        .iter()
+        .filter(|change| change.status_changed())
+        .count();
+    let output_changes = changes
+        .iter()
+        .filter(|change| change.output_changed())
+        .count();
+    let summary = Paragraph::new(Line::from(vec![
+        Span::styled(
+            format!("{} tests changed status", status_changes),
+            Style::default().fg(if status_changes > 0 {
+                Color::Yellow
+            } else {
+                Color::Green
+            }),
+        ),
+        Span::raw(", "),
+        Span::styled(
+            format!("{} outputs differ", output_changes),
+            Style::default().fg(if output_changes > 0 {
+                Color::Yellow
+            } else {
+                Color::Green
+            }),
+        ),
+    ]));
+
+    let status = |test: Option<&HistoryTest>| {
+        test.map(|test| test.status.code())
+            .unwrap_or_else(|| String::from("-"))
+    };
+    let time = |test: Option<&HistoryTest>| {
+        test.map(|test| convert_time_to_string(test.time))
+            .unwrap_or_else(|| String::from("-"))
+    };
 
-    let area = Layout::default()
-        .direction(Direction::Horizontal)
+    let rows: Vec<Row> = changes
+        .iter()
+        .map(|change| {
+            let name = app
+                .runner
+                .suite
+                .exec_name
+                .iter()
+                .position(|exec| exec == change.exec)
+                .and_then(|exec| {
+                    app.runner.suite.tests[exec]
+                        .iter()
+                        .find(|test| test.id == change.id)
+                })
+                .map(|test| test.name.as_str())
+                .unwrap_or_default();
+            let delta = change
+                .time_delta()
+                .map(|delta| format!("{:+.3}s", delta))
+                .unwrap_or_default();
+
+            let style = if change.status_changed() {
+                Style::default().fg(Color::Yellow)
+            } else if change.output_changed() {
+                Style::default().fg(Color::Blue)
+            } else {
+                Style::default()
+            };
+
+            Row::new(vec![
+                Cell::from(format!("{} {:02} {}", change.exec, change.id, name)),
+                Cell::from(status(change.before)),
+                Cell::from(status(change.after)),
+                Cell::from(time(change.before)),
+                Cell::from(time(change.after)),
+                Cell::from(delta),
+                Cell::from(if change.output_changed() {
+                    "differs"
+                } else {
+                    ""
+                }),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let selected = app.compare_state.selected().unwrap_or(0);
+    let selected_name = changes
+        .get(selected)
+        .map(|change| format!("{} {:02}", change.exec, change.id))
+        .unwrap_or_default();
+
+    let header_style = Style::default().add_modifier(Modifier::BOLD);
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(10),
+            Constraint::Length(8),
+        ],
+    )
+    .header(Row::new(vec![
+        Cell::from(Span::styled("Test", header_style)),
+        Cell::from(Span::styled("Before", header_style)),
+        Cell::from(Span::styled("After", header_style)),
+        Cell::from(Span::styled("Time before", header_style)),
+        Cell::from(Span::styled("Time after", header_style)),
+        Cell::from(Span::styled("Change", header_style)),
+        Cell::from(Span::styled("Output", header_style)),
+    ]))
+    .highlight_style(
+        Style::default()
+            .bg(Color::White)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::default().borders(Borders::ALL).title("Tests"));
+
+    let diff_items: Vec<ListItem> = if app.compare_diff.iter().all(|(sign, _)| *sign == " ") {
+        vec![ListItem::new("The outputs are the same")]
+    } else {
+        app.compare_diff
+            .iter()
+            .map(|(sign, line)| {
+                let style = match *sign {
+                    "-" => Style::default().fg(Color::Red),
+                    "+" => Style::default().fg(Color::Yellow),
+                    _ => Style::default().fg(Color::Gray),
+                };
+
+                ListItem::new(Line::from(vec![
+                    Span::styled(sign.to_string(), style),
+                    Span::styled(line.to_string(), style),
+                ]))
+            })
+            .collect()
+    };
+    let diff = List::new(diff_items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Output of {}, - before + after", selected_name)),
+    );
+
+    rect.render_widget(summary, layout[0]);
+    rect.render_stateful_widget(table, layout[1], &mut app.compare_state);
+    rect.render_widget(diff, layout[2]);
+}
+
+/// The score of the previous runs, and the status of the selected test in each of them
+fn draw_popup_history(rect: &mut Frame, app: &mut App, area: Rect) {
+    rect.render_widget(Clear, area);
+
+    if let Some((before, after)) = app.compare {
+        draw_run_comparison(rect, app, area, before, after);
+        return;
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(if app.marked_run.is_some() {
+            "Run history, select the other run and press <m> to compare"
+        } else {
+            "Run history, press <m> on two runs to compare them"
+        });
+    let inner = block.inner(area);
+    rect.render_widget(block, area);
+
+    let runs = &app.history.runs;
+    if runs.is_empty() {
+        rect.render_widget(
+            Paragraph::new("No runs recorded yet, run all the tests to record one"),
+            inner,
+        );
+        return;
+    }
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
         .constraints(
             [
-                Constraint::Percentage((100 - x) / 2),
-                Constraint::Percentage(x),
-                Constraint::Percentage((100 - x) / 2),
+                Constraint::Length(6),
+                Constraint::Min(4),
+                Constraint::Length(4),
             ]
             .as_ref(),
         )
-        .split(popup_layout[1])[1];
+        .split(inner);
+
+    // Only the last runs fit, one per column
+    let shown = runs
+        .len()
+        .saturating_sub(layout[0].width.saturating_sub(2) as usize);
+    let scores: Vec<u64> = runs[shown..]
+        .iter()
+        .map(|run| run.score.max(0.0).round() as u64)
+        .collect();
+    let max_score = runs.iter().map(|run| run.max_score).fold(0.0, f64::max);
+    let sparkline = Sparkline::default()
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "Score, last run {}/{}",
+            format_points(runs[runs.len() - 1].score),
+            format_points(runs[runs.len() - 1].max_score)
+        )))
+        .data(&scores)
+        .max(max_score.round() as u64)
+        .style(Style::default().fg(Color::Green));
+    rect.render_widget(sparkline, layout[0]);
+
+    let rows: Vec<Row> = runs
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(index, run)| {
+            let previous = index.checked_sub(1).map(|previous| &runs[previous]);
+            let failed = run.tests.iter().filter(|test| test.score == 0.0).count();
+            let lost = run
+                .tests
+                .iter()
+                .filter(|test| {
+                    previous
+                        .and_then(|previous| previous.test(&test.exec, test.id))
+                        .is_some_and(|before| test.score < before.score)
+                })
+                .count();
+
+            let marker = if app.marked_run == Some(index) {
+                "* "
+            } else {
+                "  "
+            };
+
+            Row::new(vec![
+                Cell::from(format!("{}{}", marker, run.date())),
+                Cell::from(run.commit.clone().unwrap_or_else(|| String::from("-"))),
+                Cell::from(format!(
+                    "{}/{}",
+                    format_points(run.score),
+                    format_points(run.max_score)
+                )),
+                Cell::from(failed.to_string()),
+                Cell::from(Span::styled(
+                    lost.to_string(),
+                    if lost > 0 {
+                        Style::default().fg(Color::Red)
+                    } else {
+                        Style::default()
+                    },
+                )),
+            ])
+        })
+        .collect();
+
+    let header_style = Style::default().add_modifier(Modifier::BOLD);
+    let runs_table = Table::new(
+        rows,
+        [
+            Constraint::Length(19),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(12),
+        ],
+    )
+    .header(Row::new(vec![
+        Cell::from(Span::styled("  Date", header_style)),
+        Cell::from(Span::styled("Commit", header_style)),
+        Cell::from(Span::styled("Score", header_style)),
+        Cell::from(Span::styled("Failed", header_style)),
+        Cell::from(Span::styled("Regressions", header_style)),
+    ]))
+    .highlight_style(
+        Style::default()
+            .bg(Color::White)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::default().borders(Borders::ALL).title("Runs"));
+    rect.render_stateful_widget(runs_table, layout[1], &mut app.history_state);
+
+    let index = app.test_list_state.selected().unwrap_or(0);
+    let (test_index, exec_index) = get_list_index(&app.runner.suite.tests, index);
+    let test = &app.runner.suite.tests[exec_index][test_index];
+
+    let timeline = app
+        .history
+        .timeline(&app.runner.suite.exec_name[exec_index], test.id);
+    let shown = timeline
+        .len()
+        .saturating_sub(layout[2].width.saturating_sub(2) as usize);
+    let statuses: Vec<Span> = timeline[shown..]
+        .iter()
+        .map(|result| match result {
+            None => Span::styled("·", Style::default().fg(Color::DarkGray)),
+            Some(result) => match result.status {
+                TestStatus::Timeout => Span::styled("T", Style::default().fg(Color::Blue)),
+                TestStatus::Crashed { .. } => Span::styled("C", Style::default().fg(Color::Blue)),
+                TestStatus::MemLeaks => Span::styled("M", Style::default().fg(Color::Yellow)),
+                TestStatus::Error { .. } => Span::styled("E", Style::default().fg(Color::Red)),
+                TestStatus::Passed { .. } => Span::styled("●", Style::default().fg(Color::Green)),
+                _ => Span::styled("●", Style::default().fg(Color::Red)),
+            },
+        })
+        .collect();
 
-    (area, list)
+    let test_timeline = Paragraph::new(vec![
+        Line::from(statuses),
+        Line::from(Span::styled(
+            "● passed/failed  T timeout  C crashed  M memory leaks  · not run",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ])
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("{:02} {}", test.id, test.name)),
+    );
+    rect.render_widget(test_timeline, layout[2]);
 }
 
-fn draw_test_list<'a>(app: &mut App) -> (List<'a>, Table<'a>, List<'a>, usize) {
-    let tests: Vec<ListItem> = app.test_list
-        .iter().map(| test_list_inner | test_list_inner.iter()
-        .map(|test| {
-            // Colorcode the level depending on its type
-            let style = match test.status.as_str() {
-                "0" => Style::default().fg(Color::Gray),
-                "RUNNING" => Style::default().fg(Color::Green),
-                "ERROR" => Style::default().fg(Color::Red),
-                "CRASHED" => Style::default().fg(Color::Blue),
-                "STARTING" => Style::default().fg(Color::Blue),
-                "TIMEOUT" => Style::default().fg(Color::Blue),
-                "MEMLEAKS" => Style::default().fg(Color::Blue),
-                _ => Style::default().fg(Color::Green),
-            };
+fn draw_test_list<'a>(
+    app: &mut App,
+    tests_width: u16,
+    log_width: u16,
+) -> (List<'a>, Table<'a>, List<'a>, ListState) {
+    let app_ref: &App = app;
+    let tests: Vec<ListItem> = app_ref
+        .runner
+        .suite
+        .tests
+        .iter()
+        .enumerate()
+        .flat_map(|(exec_index, test_list_inner)| {
+            test_list_inner
+                .iter()
+                .map(move |test| {
+                    // Colorcode the level depending on its type
+                    let style = match test.status {
+                        TestStatus::Pending | TestStatus::Failed => {
+                            Style::default().fg(Color::Gray)
+                        }
+                        TestStatus::Running => Style::default().fg(Color::Green),
+                        TestStatus::Error { .. } => Style::default().fg(Color::Red),
+                        TestStatus::Crashed { .. } => Style::default().fg(Color::Blue),
+                        TestStatus::Queued => Style::default().fg(Color::Blue),
+                        TestStatus::Timeout => Style::default().fg(Color::Blue),
+                        TestStatus::MemLeaks => Style::default().fg(Color::Blue),
+                        TestStatus::Passed { .. } => Style::default().fg(Color::Green),
+                    };
+                    let status = test.status.to_string();
+
+                    // Tests that lost points since the previous run
+                    let name_style = if app_ref.is_regression(exec_index, test) {
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    };
+
+                    // The name is cut to leave room for the status
+                    let name_width = (tests_width as usize).saturating_sub(status.width() + 1);
+                    let mut spans = fit(
+                        vec![Span::styled(test.name.to_string(), name_style)],
+                        name_width,
+                    );
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(status, style));
+                    let header = Line::from(spans);
+
+                    ListItem::new(header)
+                })
+                .collect::<Vec<ListItem>>()
+        })
+        .collect();
 
-            let header = Line::from(vec![
-                Span::raw(test.name.to_string()),
-                Span::raw(" ".repeat(23 - cmp::min(test.name.len() + test.status.len(), 21))),
-                Span::styled(test.status.to_string(), style),
-                ]);
-                
-            ListItem::new(header)
-        }).collect::<Vec<ListItem>>()
-    ).flatten().collect();
-        
-    let style = Style::default().fg(if app.valgrind_enabled {
+    let style = Style::default().fg(if app.runner.suite.valgrind_enabled {
         Color::Red
     } else {
         Color::Gray
@@ -259,30 +769,39 @@ fn draw_test_list<'a>(app: &mut App) -> (List<'a>, Table<'a>, List<'a>, usize) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(style)
-                .title("Tests"),
+                .title(if app.building {
+                    "Tests - building"
+                } else {
+                    "Tests"
+                }),
         );
 
     let index = app.test_list_state.selected().unwrap_or(0);
-    let (test_index, exec_index) = get_list_index(&app.test_list, index);
+    let (test_index, exec_index) = get_list_index(&app.runner.suite.tests, index);
 
-    let selected_test = app.test_list[exec_index]
+    let selected_test = app.runner.suite.tests[exec_index]
         .get(test_index)
         .expect("exists")
         .clone();
 
-    let test_detail = Table::new(vec![Row::new(vec![
-        Cell::from(Span::raw(selected_test.name)),
-        Cell::from(Span::raw(selected_test.status)),
-        Cell::from(Span::raw(convert_time_to_string(if app.valgrind_enabled {
-            selected_test.time_valgrind
-        } else {
-            selected_test.time_normal
-        }))),
-    ])], [
-        Constraint::Percentage(30),
-        Constraint::Percentage(30),
-        Constraint::Percentage(40),
-    ])
+    let test_detail = Table::new(
+        vec![Row::new(vec![
+            Cell::from(Span::raw(selected_test.name)),
+            Cell::from(Span::raw(selected_test.status.to_string())),
+            Cell::from(Span::raw(convert_time_to_string(
+                if app.runner.suite.valgrind_enabled {
+                    selected_test.time_valgrind
+                } else {
+                    selected_test.time_normal
+                },
+            ))),
+        ])],
+        [
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+        ],
+    )
     .header(Row::new(vec![
         Cell::from(Span::styled(
             "Name",
@@ -293,7 +812,7 @@ fn draw_test_list<'a>(app: &mut App) -> (List<'a>, Table<'a>, List<'a>, usize) {
             Style::default().add_modifier(Modifier::BOLD),
         )),
         Cell::from(Span::styled(
-            if app.valgrind_enabled {
+            if app.runner.suite.valgrind_enabled {
                 "Time Valgrind"
             } else {
                 "Time"
@@ -303,44 +822,89 @@ fn draw_test_list<'a>(app: &mut App) -> (List<'a>, Table<'a>, List<'a>, usize) {
     ]))
     .block(Block::default().borders(Borders::ALL).title("Details"));
 
-    let mut index = app.diff.len();
-    let mut first_diff: usize = usize::MAX;
-    let mut log_items: Vec<ListItem> = Vec::new();
-
-    for (i, (sign, line)) in app.diff.iter().enumerate() {
-        if i == 10000 {
-            index = i;
-            break;
-        }
-
-        let style = match *sign {
-            "-" => Style::default().fg(Color::Red),
-            "+" => Style::default().fg(Color::Yellow),
-            " " => Style::default().fg(Color::Gray),
-            _ => Style::default(),
+    let pattern = search_pattern(&app.search, SearchPanel::TestLog);
+    let diff = Arc::clone(&app.diff);
+    let source = LayoutSource {
+        diff: diff.id,
+        side_by_side: app.side_by_side,
+        search: pattern
+            .as_ref()
+            .map(|pattern| (pattern.as_str().to_string(), app.search.case_sensitive)),
+    };
+    // Every line is only looked at again once the diff or the search change
+    if !app.diff_view.is_laid_out(&source) {
+        let matched: Vec<bool> = match &pattern {
+            Some(pattern) if app.side_by_side => diff
+                .rows
+                .iter()
+                .map(|row| {
+                    row.sides(&diff.lines)
+                        .into_iter()
+                        .flatten()
+                        .any(|(_, line)| pattern.is_match(line))
+                })
+                .collect(),
+            Some(pattern) => diff
+                .lines
+                .iter()
+                .map(|(_, line)| pattern.is_match(line))
+                .collect(),
+            None => Vec::new(),
         };
-
-        if *sign != " " && first_diff > i {
-            first_diff = i;
+        let changed = if app.side_by_side {
+            &diff.changed_rows
+        } else {
+            &diff.changed_lines
+        };
+        app.diff_view.layout(source, changed, &matched);
+
+        if pattern.is_some() {
+            app.search.rows = app
+                .diff_view
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| matches!(entry, DiffEntry::Line(line) if matched[*line]))
+                .map(|(row, _)| row)
+                .collect();
         }
-
-        let item = ListItem::new(Line::from(vec![
-            Span::styled(sign.to_string(), style),
-            Span::styled(line.to_string(), style),
-        ]));
-
-        log_items.push(item);
     }
+    if pattern.is_some() {
+        if let Some(row) = app.search.take_jump() {
+            app.show_match(row);
+        }
+    }
+    app.state.set_diffsize(app.diff_view.rows.len());
 
-    app.state.set_diffsize(index);
-
-    if log_items.len() < app.diff.len() {
-        log_items.push(ListItem::new(Line::from(vec![Span::raw(
-            "Showing only the first 10000 lines",
-        )])));
-        app.state.set_diffsize(index + 1);
+    // The first difference is selected once the test log is focused
+    if app.windows_list_state.selected() == Some(1) {
+        if app.log_list_state.selected().is_none() {
+            let first_diff = app.diff_view.hunks.first().copied().unwrap_or(0);
+            app.log_list_state.select(Some(first_diff));
+        }
+    } else {
+        app.log_list_state.select(None);
     }
 
+    // Only the rows shown are drawn
+    let selected = app.log_list_state.selected();
+    let window = app.diff_view.window(selected);
+    let mut log_state = ListState::default();
+    log_state.select(selected.map(|selected| selected - window.start));
+
+    let entries = &app.diff_view.rows[window];
+    let log_items = if app.side_by_side {
+        side_by_side_items(
+            &diff,
+            entries,
+            log_width,
+            pattern.as_ref(),
+            app.show_whitespace,
+        )
+    } else {
+        unified_items(&diff, entries, pattern.as_ref(), app.show_whitespace)
+    };
+
     let test_log = List::new(log_items)
         .highlight_style(
             Style::default()
@@ -349,27 +913,448 @@ fn draw_test_list<'a>(app: &mut App) -> (List<'a>, Table<'a>, List<'a>, usize) {
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol(">> ")
-        .block(Block::default().borders(Borders::ALL).title("Test log"));
+        .block(Block::default().borders(Borders::ALL).title(log_title(app)));
+
+    (test_list, test_detail, test_log, log_state)
+}
+
+/// The input of the selected test, with the command that printed the selected line of
+/// the test log highlighted and the first one that printed a difference in red
+fn draw_input<'a>(app: &mut App, page: usize) -> (List<'a>, ListState) {
+    let index = app.test_list_state.selected().unwrap_or(0);
+    let (_, exec_index) = get_list_index(&app.runner.suite.tests, index);
+
+    let (selected, diverged) = match app
+        .runner
+        .suite
+        .commands
+        .get(&app.runner.suite.exec_name[exec_index])
+    {
+        Some(commands) => {
+            let positions = if app.side_by_side {
+                &app.diff.row_ref_positions
+            } else {
+                &app.diff.ref_positions
+            };
+            let command_at = |row: usize| match app.diff_view.rows.get(row) {
+                Some(DiffEntry::Line(line)) => positions
+                    .get(*line)
+                    .and_then(|&line| commands.command_at(&app.current_input, line)),
+                _ => None,
+            };
+
+            (
+                app.log_list_state.selected().and_then(command_at),
+                app.diff_view.hunks.first().and_then(|&row| command_at(row)),
+            )
+        }
+        None => (None, None),
+    };
+    let selected = selected.or(diverged);
+
+    // Only the lines shown are drawn
+    let count = app.current_input.lines().count();
+    let window = scroll(app.input_offset, selected, page, count);
+    app.input_offset = window.start;
+    let mut state = ListState::default();
+    state.select(selected.map(|selected| selected - window.start));
+
+    let width = count.to_string().len();
+    let items: Vec<ListItem> = app
+        .current_input
+        .lines()
+        .enumerate()
+        .skip(window.start)
+        .take(window.len())
+        .map(|(line, command)| {
+            let style = if Some(line) == diverged {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
 
-    if first_diff == usize::MAX {
-        first_diff = 0;
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:>width$} ", line + 1, width = width),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(command.to_string(), style),
+            ]))
+        })
+        .collect();
+
+    let title = match diverged {
+        Some(line) => format!("Input - differs at {}", line + 1),
+        None => String::from("Input"),
+    };
+
+    let input = List::new(items)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    (input, state)
+}
+
+fn log_title(app: &App) -> String {
+    let mut title = String::from("Test log");
+    if app.side_by_side {
+        title.push_str(" - side by side");
     }
 
-    (test_list, test_detail, test_log, first_diff)
+    let hunks = app.diff_view.hunks.len();
+    let current = app
+        .log_list_state
+        .selected()
+        .and_then(|selected| app.diff_view.current_hunk(selected));
+    match current {
+        Some(current) => title.push_str(&format!(" - difference {} of {}", current, hunks)),
+        None if hunks > 0 => title.push_str(&format!(" - {} differences", hunks)),
+        None => {}
+    }
+
+    // The most common reason for outputs that look the same
+    if app.diff.whitespace_only {
+        title.push_str(" - only whitespace differs");
+    }
+
+    title
+}
+
+/// The row standing for a run of unchanged lines
+fn folded_item<'a>(count: usize, indent: usize) -> ListItem<'a> {
+    ListItem::new(Line::from(Span::styled(
+        format!("{}··· {} unchanged lines ···", " ".repeat(indent), count),
+        Style::default()
+            .fg(Color::DarkGray)
+            .add_modifier(Modifier::ITALIC),
+    )))
 }
 
-fn draw_final_score<'a>(app: &App) -> Paragraph<'a> {
-    let score = app.calculate_score();
+/// The rows of the unified diff
+fn unified_items<'a>(
+    diff: &TestDiff,
+    entries: &[DiffEntry],
+    pattern: Option<&Regex>,
+    show_whitespace: bool,
+) -> Vec<ListItem<'a>> {
+    entries
+        .iter()
+        .map(|entry| {
+            let i = match *entry {
+                DiffEntry::Line(i) => i,
+                DiffEntry::Unchanged { count, .. } => return folded_item(count, 1),
+            };
+            let (sign, line) = &diff.lines[i];
+            let style = match *sign {
+                "-" => Style::default().fg(Color::Red),
+                "+" => Style::default().fg(Color::Yellow),
+                " " => Style::default().fg(Color::Gray),
+                _ => Style::default(),
+            };
+
+            let words = diff.pairs[i].and_then(|pair| {
+                let other = &diff.lines[pair].1;
+                if *sign == "-" {
+                    inline_diff(line, other).map(|(removed, _)| removed)
+                } else {
+                    inline_diff(other, line).map(|(_, added)| added)
+                }
+            });
+
+            let mut spans = vec![Span::styled(sign.to_string(), style)];
+            spans.extend(visible(
+                highlight(line_spans(line, words, style), pattern),
+                show_whitespace,
+            ));
+            if diff.whitespace[i] {
+                spans.push(Span::styled(
+                    "  ≈ whitespace only",
+                    Style::default()
+                        .fg(Color::Magenta)
+                        .add_modifier(Modifier::ITALIC),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect()
+}
 
-    let style = match score {
-        i if i <= 0 => Style::default().fg(Color::Red),
-        100 => Style::default().fg(Color::Green),
-        120 => Style::default().fg(Color::Green),
+/// The rows of the side by side diff, fitted in `width` columns
+fn side_by_side_items<'a>(
+    diff: &TestDiff,
+    entries: &[DiffEntry],
+    width: u16,
+    pattern: Option<&Regex>,
+    show_whitespace: bool,
+) -> Vec<ListItem<'a>> {
+    let last_line = diff
+        .rows
+        .last()
+        .map(|row| {
+            cmp::max(
+                row.reference.map_or(0, |(number, _)| number),
+                row.output.map_or(0, |(number, _)| number),
+            )
+        })
+        .unwrap_or(0);
+    let number_width = last_line.to_string().len();
+    // Borders, highlight symbol and separator
+    let text_width = (width as usize).saturating_sub(2 + 3 + 3 + 2 * (number_width + 1)) / 2;
+
+    let side = |numbered: Option<(usize, &str)>, words: Option<InlineChanges>, style: Style| {
+        match numbered {
+            Some((number, text)) => {
+                let mut spans = vec![Span::styled(
+                    format!("{:>width$} ", number, width = number_width),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                spans.extend(fit(
+                    visible(
+                        highlight(line_spans(text, words, style), pattern),
+                        show_whitespace,
+                    ),
+                    text_width,
+                ));
+                spans
+            }
+            None => vec![Span::raw(" ".repeat(number_width + 1 + text_width))],
+        }
+    };
+
+    entries
+        .iter()
+        .map(|entry| {
+            let row = match *entry {
+                DiffEntry::Line(i) => &diff.rows[i],
+                DiffEntry::Unchanged { count, .. } => return folded_item(count, number_width + 1),
+            };
+            let changed = row.changed(&diff.lines);
+            let [reference, output] = row.sides(&diff.lines);
+            let (reference_style, output_style) = if changed {
+                (
+                    Style::default().fg(Color::Red),
+                    Style::default().fg(Color::Yellow),
+                )
+            } else {
+                (
+                    Style::default().fg(Color::Gray),
+                    Style::default().fg(Color::Gray),
+                )
+            };
+
+            let (removed, added) = match (reference, output) {
+                (Some((_, reference)), Some((_, output))) if changed => {
+                    inline_diff(reference, output).unzip()
+                }
+                _ => (None, None),
+            };
+
+            let mut spans = side(reference, removed, reference_style);
+            // The rows that changed only in whitespace are flagged in the separator
+            if row.whitespace_only(&diff.lines) {
+                spans.push(Span::styled(" ≈ ", Style::default().fg(Color::Magenta)));
+            } else {
+                spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
+            }
+            spans.extend(side(output, added, output_style));
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect()
+}
+
+/// Replaces the whitespace and invisible characters of the spans with dimmed glyphs
+fn visible(spans: Vec<Span<'_>>, show_whitespace: bool) -> Vec<Span<'_>> {
+    if !show_whitespace {
+        return spans;
+    }
+
+    let mut shown = Vec::new();
+    for span in spans {
+        let mut content = String::new();
+        for c in span.content.chars() {
+            if c == '\n' {
+                continue;
+            }
+            match whitespace_glyph(c) {
+                Some(glyph) => {
+                    if !content.is_empty() {
+                        shown.push(Span::styled(std::mem::take(&mut content), span.style));
+                    }
+                    shown.push(Span::styled(
+                        glyph.to_string(),
+                        span.style.fg(Color::DarkGray),
+                    ));
+                }
+                None => content.push(c),
+            }
+        }
+        if !content.is_empty() {
+            shown.push(Span::styled(content, span.style));
+        }
+    }
+
+    shown
+}
+
+/// The search in this panel, unless its query is not a valid regular expression
+fn search_pattern(search: &Search, panel: SearchPanel) -> Option<Regex> {
+    if !search.is_active_in(panel) {
+        return None;
+    }
+
+    search.pattern()?.ok()
+}
+
+/// Marks the parts of the spans matching the search
+fn highlight<'a>(spans: Vec<Span<'a>>, pattern: Option<&Regex>) -> Vec<Span<'a>> {
+    let Some(pattern) = pattern else {
+        return spans;
+    };
+    let text: String = spans.iter().map(|span| span.content.as_ref()).collect();
+    let matches: Vec<Range<usize>> = pattern
+        .find_iter(&text)
+        .map(|found| found.range())
+        .filter(|range| !range.is_empty())
+        .collect();
+    if matches.is_empty() {
+        return spans;
+    }
+
+    let match_style = Style::default().bg(Color::LightYellow).fg(Color::Black);
+    let mut highlighted = Vec::new();
+    let mut offset = 0;
+    for span in spans {
+        let content = span.content.as_ref();
+        let end = offset + content.len();
+
+        let mut done = offset;
+        for range in matches
+            .iter()
+            .filter(|range| range.start < end && range.end > offset)
+        {
+            let (from, to) = (range.start.max(offset), range.end.min(end));
+            if from > done {
+                highlighted.push(Span::styled(
+                    content[done - offset..from - offset].to_string(),
+                    span.style,
+                ));
+            }
+            highlighted.push(Span::styled(
+                content[from - offset..to - offset].to_string(),
+                span.style.patch(match_style),
+            ));
+            done = to;
+        }
+        if done < end {
+            highlighted.push(Span::styled(
+                content[done - offset..].to_string(),
+                span.style,
+            ));
+        }
+
+        offset = end;
+    }
+
+    highlighted
+}
+
+/// A line of the diff, with the words that differ from the line paired with it
+/// shown in reverse
+fn line_spans<'a>(line: &str, words: Option<InlineChanges>, style: Style) -> Vec<Span<'a>> {
+    match words {
+        Some(words) => words
+            .into_iter()
+            .map(|(changed, word)| {
+                Span::styled(
+                    word,
+                    if changed {
+                        style.add_modifier(Modifier::REVERSED)
+                    } else {
+                        style
+                    },
+                )
+            })
+            .collect(),
+        None => vec![Span::styled(line.to_string(), style)],
+    }
+}
+
+/// Pads or cuts the spans to exactly `width` terminal columns, marking where they
+/// were cut
+fn fit(spans: Vec<Span<'_>>, width: usize) -> Vec<Span<'_>> {
+    let total: usize = spans.iter().map(|span| span.content.width()).sum();
+    if total <= width {
+        let mut spans = spans;
+        spans.push(Span::raw(" ".repeat(width - total)));
+        return spans;
+    }
+
+    let mut fitted = Vec::new();
+    let mut used = 0;
+    'spans: for span in spans {
+        let mut content = String::new();
+        for c in span.content.chars() {
+            let char_width = c.width().unwrap_or(0);
+            if used + char_width + 1 > width {
+                fitted.push(Span::styled(content, span.style));
+                break 'spans;
+            }
+            content.push(c);
+            used += char_width;
+        }
+        fitted.push(Span::styled(content, span.style));
+    }
+    if width > 0 {
+        fitted.push(Span::raw("…"));
+        used += 1;
+    }
+    fitted.push(Span::raw(" ".repeat(width - used)));
+
+    fitted
+}
+
+fn draw_group_scores<'a>(breakdown: &ScoreBreakdown) -> Table<'a> {
+    let rows: Vec<Row> = breakdown
+        .rows()
+        .map(|row| {
+            let style = match row.score {
+                score if score <= 0.0 => Style::default().fg(Color::Gray),
+                score if score >= row.max => Style::default().fg(Color::Green),
+                _ => Style::default(),
+            };
+
+            Row::new(vec![
+                Cell::from(Span::raw(format!("{}{}", "  ".repeat(row.depth), row.name))),
+                Cell::from(
+                    Line::from(Span::styled(
+                        format!("{}/{}", format_points(row.score), format_points(row.max)),
+                        style,
+                    ))
+                    .alignment(Alignment::Right),
+                ),
+            ])
+        })
+        .collect();
+
+    Table::new(rows, [Constraint::Min(10), Constraint::Length(9)])
+        .block(Block::default().borders(Borders::ALL).title("Subtotals"))
+        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+fn draw_final_score<'a>(breakdown: &ScoreBreakdown) -> Paragraph<'a> {
+    let style = match breakdown.total {
+        score if score <= 0.0 => Style::default().fg(Color::Red),
+        score if score >= breakdown.max => Style::default().fg(Color::Green),
         _ => Style::default(),
     };
 
     Paragraph::new(vec![Line::from(Span::styled(
-        format!("{}/100", score),
+        format!(
+            "{}/{}",
+            format_points(breakdown.total),
+            format_points(breakdown.max)
+        ),
         style,
     ))])
     .alignment(Alignment::Right)
@@ -382,26 +1367,69 @@ fn draw_final_score<'a>(app: &App) -> Paragraph<'a> {
     )
 }
 
-fn check_size(rect: &Rect) {
-    if rect.width < 52 {
-        panic!("Require width >= 52, (got {})", rect.width);
-    }
-    if rect.height < 24 {
-        panic!("Require height >= 24, (got {})", rect.height);
-    }
+/// Shown instead of the checker while the terminal is smaller than it needs
+fn draw_too_small(rect: &mut Frame, size: Rect) {
+    let text = vec![
+        Line::from(Span::styled(
+            "Terminal too small",
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(format!(
+            "{}x{}, the checker needs at least {}x{}",
+            size.width, size.height, MIN_WIDTH, MIN_HEIGHT
+        )),
+    ];
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+
+    let height = cmp::min(size.height, 4);
+    let area = Rect::new(
+        size.x,
+        size.y + (size.height - height) / 2,
+        size.width,
+        height,
+    );
+    rect.render_widget(paragraph, area);
 }
 
-fn draw_help(actions: &Actions) -> Table {
+/// Fits the longest test name next to its status, without taking most of the body
+fn test_list_width(app: &App, body_width: u16) -> u16 {
+    let longest = app
+        .runner
+        .suite
+        .tests
+        .iter()
+        .flatten()
+        .map(|test| test.name.width())
+        .max()
+        .unwrap_or(0);
+    // The borders, a space and the longest status, such as MEMLEAKS
+    let width = (longest + 11).min(u16::MAX as usize) as u16;
+
+    let most = cmp::max(body_width * 2 / 5, MIN_TESTS_WIDTH);
+    width.clamp(MIN_TESTS_WIDTH, most)
+}
+
+/// The help panel, with the key of each of its rows
+fn draw_help<'a>(actions: &Actions, groups: &[Group]) -> (Table<'a>, Vec<Key>) {
     let key_style = Style::default().fg(Color::LightCyan);
     let help_style = Style::default().fg(Color::Gray);
 
     let mut rows = vec![];
+    let mut keys = vec![];
     for action in actions.actions().iter() {
         let mut first = true;
-        for key in action.keys() {
+        for key in actions.keys(action) {
             let help = if first {
                 first = false;
-                action.to_string()
+                match action {
+                    Action::RunGroup(index) => match groups.get(*index) {
+                        Some(group) => format!("Run {}", group.name),
+                        None => action.to_string(),
+                    },
+                    _ => action.to_string(),
+                }
             } else {
                 String::from("")
             };
@@ -410,22 +1438,25 @@ fn draw_help(actions: &Actions) -> Table {
                 Cell::from(Span::styled(help, help_style)),
             ]);
             rows.push(row);
+            keys.push(*key);
         }
     }
 
-    Table::new(rows, [Constraint::Length(11), Constraint::Min(20)])
+    let help = Table::new(rows, [Constraint::Length(11), Constraint::Min(20)])
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Plain)
                 .title("Help"),
         )
-        .column_spacing(1)
+        .column_spacing(1);
+
+    (help, keys)
 }
 
-fn draw_logs<'a>() -> TuiLoggerWidget<'a> {
+fn draw_logs<'a>(focused: bool) -> TuiLoggerWidget<'a> {
     TuiLoggerWidget::default()
-        .block(Block::bordered().title("Logs"))
+        .block(logs_block(focused, "Logs"))
         .style_error(Style::default().fg(Color::Red))
         .style_debug(Style::default().fg(Color::Green))
         .style_warn(Style::default().fg(Color::Yellow))
@@ -434,6 +1465,105 @@ fn draw_logs<'a>() -> TuiLoggerWidget<'a> {
         .output_level(Some(TuiLoggerLevelOutput::Long))
 }
 
+fn logs_block(focused: bool, title: &str) -> Block<'_> {
+    Block::bordered().title(title).border_style(if focused {
+        Style::default().fg(Color::LightCyan)
+    } else {
+        Style::default()
+    })
+}
+
+fn level_style(level: Level) -> Style {
+    match level {
+        Level::Error => Style::default().fg(Color::Red),
+        Level::Warn => Style::default().fg(Color::Yellow),
+        Level::Info => Style::default().fg(Color::Cyan),
+        Level::Debug => Style::default().fg(Color::Green),
+        Level::Trace => Style::default().fg(Color::Magenta),
+    }
+}
+
+/// The logs with the matches of the search, which tui-logger cannot show
+fn draw_searched_logs<'a>(app: &mut App, pattern: &Regex, focused: bool) -> List<'a> {
+    let lines = logs::lines();
+
+    app.search.rows = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| pattern.is_match(&line.message))
+        .map(|(row, _)| row)
+        .collect();
+    if let Some(row) = app.search.take_jump() {
+        app.show_match(row);
+    }
+    if app.logs_state.selected().is_none() {
+        app.logs_state.select(lines.len().checked_sub(1));
+    }
+
+    let items: Vec<ListItem> = lines
+        .into_iter()
+        .map(|line| {
+            let style = level_style(line.level);
+            let mut spans = vec![Span::styled(
+                format!("{}:{:<5}:{}:", line.time, line.level, line.target),
+                style,
+            )];
+            spans.extend(highlight(
+                vec![Span::styled(line.message, style)],
+                Some(pattern),
+            ));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    List::new(items)
+        .highlight_symbol(">> ")
+        .block(logs_block(focused, "Logs - search"))
+}
+
+/// The search prompt, on the last row of the screen
+fn draw_search_prompt(rect: &mut Frame, app: &App, size: Rect) {
+    let search = &app.search;
+    let area = Rect::new(size.x, size.y + size.height - 1, size.width, 1);
+
+    let status = match search.pattern() {
+        Some(Err(_)) => Span::styled("invalid regex", Style::default().fg(Color::Red)),
+        _ if search.rows.is_empty() => Span::raw("no matches"),
+        _ => match search.current_match() {
+            Some(current) => Span::raw(format!("match {} of {}", current, search.rows.len())),
+            None => Span::raw(format!("{} matches", search.rows.len())),
+        },
+    };
+    let flag = |enabled: bool| if enabled { "on" } else { "off" };
+
+    let mut spans = vec![
+        Span::styled("/", Style::default().fg(Color::LightCyan)),
+        Span::raw(search.query.clone()),
+        Span::raw("  "),
+        status,
+    ];
+    if search.editing {
+        spans.push(Span::styled(
+            format!(
+                "  <Alt+c> case {}  <Alt+r> regex {}",
+                flag(search.case_sensitive),
+                flag(search.regex)
+            ),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+
+    rect.render_widget(Clear, area);
+    rect.render_widget(Paragraph::new(Line::from(spans)), area);
+    if search.editing {
+        let cursor = area.x + 1 + search.query.width() as u16;
+        rect.set_cursor(
+            cmp::min(cursor, area.x + area.width.saturating_sub(1)),
+            area.y,
+        );
+    }
+}
+
 fn convert_time_to_string(time: f64) -> String {
     let mut seconds = String::default();
 
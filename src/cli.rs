@@ -0,0 +1,136 @@
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+
+use hw_checker::suite::LoadOptions;
+
+/// An interactive checker built for the homeworks
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub options: Options,
+
+    /// Same as the `run` subcommand, kept for older scripts
+    #[arg(long, hide = true)]
+    pub legacy: bool,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Open the interactive checker (default)
+    Tui,
+    /// Run the tests and the checkstyle, printing the results.
+    ///
+    /// Exits with 1 when a test fails and with 2 when the homework cannot be built.
+    Run {
+        /// Write the report to this file instead of stdout, needs a `--format`
+        /// other than text
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<String>,
+    },
+    /// List the tests of the suite
+    List,
+    /// Run only the coding style checker
+    CheckStyle,
+    /// Run `make clean` and remove the outputs of the tests
+    Clean {
+        /// Also remove the stored results and the history of the runs
+        #[arg(long)]
+        results: bool,
+    },
+    /// Write the refs from the outputs of a reference solution, for the course staff.
+    ///
+    /// Shows the refs that changed and the time of every test before writing them.
+    Bless {
+        /// Directory holding the built executables of the reference solution
+        #[arg(value_name = "DIR")]
+        solution: String,
+        /// Write the refs without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct Options {
+    /// Test suite manifest, a `.json` file is read as a legacy `data.json`
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<String>,
+
+    /// Directory holding the input, ref and output directories
+    #[arg(long, global = true, value_name = "DIR")]
+    pub test_path: Option<String>,
+
+    /// Only use the tests whose `NN-exec` or name contain this text
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub filter: Option<String>,
+
+    /// Number of tests run at the same time [default: number of cores]
+    #[arg(long, short, global = true)]
+    pub jobs: Option<usize>,
+
+    /// Run the tests under valgrind
+    #[arg(long, global = true, action = ArgAction::SetTrue, overrides_with = "no_valgrind")]
+    pub valgrind: bool,
+
+    /// Run the tests without valgrind
+    #[arg(long, global = true, action = ArgAction::SetTrue, overrides_with = "valgrind")]
+    pub no_valgrind: bool,
+
+    /// Multiply the timeout of every test, for slower machines
+    #[arg(long, global = true, value_name = "FACTOR")]
+    pub timeout_scale: Option<f64>,
+
+    /// Stream the progress of the runs as JSON lines to a file, to `unix:PATH`
+    /// or to `tcp:HOST:PORT`
+    #[arg(long, global = true, value_name = "TARGET")]
+    pub events: Option<String>,
+
+    /// Output format of `list` and of the `run` report
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+    /// JUnit XML, only for `run`
+    Junit,
+    /// Test Anything Protocol, only for `run`
+    Tap,
+}
+
+impl Cli {
+    /// The subcommand to run, `tui` when none is given
+    pub fn command(&self) -> Command {
+        match &self.command {
+            Some(command) => command.clone(),
+            None if self.legacy => Command::Run { output: None },
+            None => Command::Tui,
+        }
+    }
+}
+
+impl Options {
+    /// `None` when neither `--valgrind` nor `--no-valgrind` were given
+    pub fn valgrind(&self) -> Option<bool> {
+        match (self.valgrind, self.no_valgrind) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            manifest: self.config.clone(),
+            test_path: self.test_path.clone(),
+            filter: self.filter.clone(),
+            valgrind: self.valgrind(),
+            timeout_scale: self.timeout_scale,
+        }
+    }
+}
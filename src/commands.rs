@@ -0,0 +1,306 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use eyre::{bail, Result, WrapErr};
+use log::error;
+use serde::Serialize;
+use tokio::io::AsyncWrite;
+use tokio::net::{TcpStream, UnixStream};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+use hw_checker::report::{diff_excerpt, html, json, junit, tap, Report, HTML_REPORT};
+use hw_checker::runner::events::write_json_lines;
+use hw_checker::runner::{Blessed, EventBus, Runner, Timing, TIMINGS_FILE};
+use hw_checker::suite::{
+    History, LoadOptions, Suite, HISTORY_OUTPUTS_PATH, HISTORY_PATH, RESULTS_PATH,
+};
+
+use crate::cli::Format;
+use crate::legacy::run_make_clean;
+
+#[derive(Serialize)]
+struct ListedTest<'a> {
+    exec: &'a str,
+    id: usize,
+    name: &'a str,
+    score: usize,
+    timeout: u64,
+    status: String,
+}
+
+/// Prints every test of the suite, with the status of its last run
+pub fn list_tests(suite: &Suite, format: Format) -> Result<()> {
+    match format {
+        Format::Text => {
+            for (exec, tests) in suite.exec_name.iter().zip(suite.tests.iter()) {
+                println!("==== {exec} ====");
+
+                for test in tests {
+                    println!(
+                        "{:02}  {:<30} {:>3} points {:>7} ms  {}",
+                        test.id, test.name, test.test_score, test.timeout, test.status
+                    );
+                }
+                println!();
+            }
+        }
+        Format::Json => {
+            let tests: Vec<ListedTest> = suite
+                .exec_name
+                .iter()
+                .zip(suite.tests.iter())
+                .flat_map(|(exec, tests)| {
+                    tests.iter().map(move |test| ListedTest {
+                        exec,
+                        id: test.id,
+                        name: &test.name,
+                        score: test.test_score,
+                        timeout: test.timeout,
+                        status: test.status.code(),
+                    })
+                })
+                .collect();
+
+            println!("{}", serde_json::to_string_pretty(&tests).unwrap());
+        }
+        Format::Junit | Format::Tap => bail!("The tests can only be listed as text or json"),
+    }
+
+    Ok(())
+}
+
+/// Writes the report of a run to `output`, or to stdout when it is not given.
+///
+/// The text report is the progress printed while running, so nothing is written.
+pub fn write_report(report: &Report, format: Format, output: Option<&str>) -> Result<()> {
+    let content = match format {
+        Format::Text => return Ok(()),
+        Format::Json => format!("{}\n", json::render(report)?),
+        Format::Junit => junit::render(report),
+        Format::Tap => tap::render(report),
+    };
+
+    match output {
+        Some(path) => fs::write(path, content).wrap_err_with(|| format!("Cannot write {}", path)),
+        None => {
+            print!("{content}");
+            Ok(())
+        }
+    }
+}
+
+/// Writes the HTML report in the test directory, returning its path
+pub fn write_html_report(report: &Report) -> Result<String> {
+    let path = format!("{}{}", report.test_path, HTML_REPORT);
+    fs::write(&path, html::render(report)).wrap_err_with(|| format!("Cannot write {}", path))?;
+
+    Ok(path)
+}
+
+/// Adds the results of the run to the history
+pub fn record_run(runner: &Runner) -> Result<()> {
+    // There is no history before the first full run
+    let mut history = History::load(HISTORY_PATH).unwrap_or_default();
+    history.push(runner.history_run().store(HISTORY_OUTPUTS_PATH));
+    history.save(HISTORY_PATH, HISTORY_OUTPUTS_PATH)
+}
+
+/// Runs `make clean` and removes the files written by the tests
+pub async fn clean(suite: &Suite, results: bool) {
+    run_make_clean().await;
+
+    let mut removed = 0;
+    for (exec, tests) in suite.exec_name.iter().zip(suite.tests.iter()) {
+        for test in tests {
+            for extension in ["out", "valgrind"] {
+                let path = format!(
+                    "{}output/{:02}-{}.{}",
+                    suite.test_path, test.id, exec, extension
+                );
+                if fs::remove_file(&path).is_ok() {
+                    removed += 1;
+                }
+            }
+        }
+    }
+    println!("Removed {removed} output files");
+
+    if results {
+        for path in [RESULTS_PATH, HISTORY_PATH] {
+            if !Path::new(path).exists() {
+                continue;
+            }
+
+            match fs::remove_file(path) {
+                Ok(_) => println!("Removed {path}"),
+                Err(err) => println!("Error {:?}", err),
+            }
+        }
+
+        if Path::new(HISTORY_OUTPUTS_PATH).exists() {
+            match fs::remove_dir_all(HISTORY_OUTPUTS_PATH) {
+                Ok(_) => println!("Removed {HISTORY_OUTPUTS_PATH}"),
+                Err(err) => println!("Error {:?}", err),
+            }
+        }
+    }
+}
+
+/// Runs the reference solution in `solution` on every input and writes the refs that
+/// changed, once they are accepted
+pub async fn bless(options: &LoadOptions, solution: &str, yes: bool) -> Result<()> {
+    let suite = Suite::load_inputs(options)?;
+    for warning in suite.warnings.iter() {
+        println!("Warning: {}", warning);
+    }
+
+    let mut blessed = Vec::new();
+    let mut failed = 0;
+    for (exec, tests) in suite.exec_name.iter().zip(suite.tests.iter()) {
+        println!("==== {exec} ====");
+
+        for test in tests {
+            match Blessed::run(Path::new(solution), &suite.test_path, exec, test).await {
+                Ok(test) => {
+                    let label = match (&test.old, test.changed()) {
+                        (None, _) => "NEW",
+                        (Some(_), true) => "CHANGED",
+                        (Some(_), false) => "SAME",
+                    };
+                    println!(
+                        "Test {:02}{}{}  {:>8.0} ms",
+                        test.id,
+                        ".".repeat(33 - label.len()),
+                        label,
+                        test.time * 1000.0
+                    );
+                    blessed.push(test);
+                }
+                Err(err) => {
+                    println!("Test {:02}: {}", test.id, err);
+                    failed += 1;
+                }
+            }
+        }
+        println!();
+    }
+
+    let changed: Vec<&Blessed> = blessed.iter().filter(|test| test.changed()).collect();
+    for test in changed.iter() {
+        println!("==== {:02}-{} ====", test.id, test.exec);
+        match &test.old {
+            Some(old) => println!("{}\n", diff_excerpt(old, &test.new)),
+            None => println!("New ref, {} lines\n", test.new.lines().count()),
+        }
+    }
+
+    println!("==== Timeouts ====");
+    println!(
+        "{:<20}{:>12}{:>12}{:>12}",
+        "Test", "Time", "Timeout", "Suggested"
+    );
+    for test in blessed.iter() {
+        let suggested = test.suggested_timeout();
+        println!(
+            "{:<20}{:>9.0} ms{:>9} ms{:>9} ms{}",
+            format!("{:02}-{}", test.id, test.exec),
+            test.time * 1000.0,
+            test.timeout,
+            suggested,
+            if suggested > test.timeout {
+                "  too tight"
+            } else {
+                ""
+            }
+        );
+    }
+    println!();
+
+    let path = write_timings(&suite.test_path, &blessed)?;
+    println!("Wrote the timings to {path}");
+
+    if failed > 0 {
+        println!("The reference solution failed {failed} test(s), their refs are kept");
+    }
+    if changed.is_empty() {
+        println!("Every ref is up to date");
+        return Ok(());
+    }
+
+    if !yes {
+        print!("Write {} ref(s)? [y/N] ", changed.len());
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            println!("No ref was written");
+            return Ok(());
+        }
+    }
+
+    for test in changed.iter() {
+        test.write()
+            .await
+            .wrap_err_with(|| format!("Cannot write {}", test.path))?;
+    }
+    println!("Wrote {} ref(s)", changed.len());
+
+    Ok(())
+}
+
+/// Writes the time of the reference solution on every test and the suggested timeouts
+/// in the test directory, returning its path
+fn write_timings(test_path: &str, blessed: &[Blessed]) -> Result<String> {
+    let path = format!("{}{}", test_path, TIMINGS_FILE);
+    let timings: Vec<Timing> = blessed.iter().map(Blessed::timing).collect();
+    fs::write(&path, serde_json::to_string_pretty(&timings)?)
+        .wrap_err_with(|| format!("Cannot write {}", path))?;
+
+    Ok(path)
+}
+
+/// Streams the progress as JSON lines to `target`: a file, `unix:PATH` or `tcp:HOST:PORT`
+pub async fn stream_events(events: &EventBus, target: &str) -> Result<JoinHandle<()>> {
+    let receiver = events.subscribe();
+    let writer: Box<dyn AsyncWrite + Unpin + Send> =
+        if let Some(path) = target.strip_prefix("unix:") {
+            Box::new(
+                UnixStream::connect(path)
+                    .await
+                    .wrap_err_with(|| format!("Cannot connect to {}", path))?,
+            )
+        } else if let Some(address) = target.strip_prefix("tcp:") {
+            Box::new(
+                TcpStream::connect(address)
+                    .await
+                    .wrap_err_with(|| format!("Cannot connect to {}", address))?,
+            )
+        } else {
+            Box::new(
+                tokio::fs::File::create(target)
+                    .await
+                    .wrap_err_with(|| format!("Cannot create {}", target))?,
+            )
+        };
+
+    let target = target.to_string();
+    Ok(tokio::spawn(async move {
+        if let Err(err) = write_json_lines(receiver, writer).await {
+            error!("Cannot write the events to {}: {}", target, err);
+        }
+    }))
+}
+
+/// Drops the runner, which closes the event stream, and waits for the last events to be
+/// written
+pub async fn finish_events(runner: Runner, writer: Option<JoinHandle<()>>) {
+    drop(runner);
+
+    if let Some(writer) = writer {
+        let _ = timeout(Duration::from_secs(5), writer).await;
+    }
+}
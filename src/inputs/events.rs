@@ -3,8 +3,10 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use log::error;
+use tokio::sync::{Mutex, OwnedMutexGuard};
 
 use super::key::Key;
+use super::mouse::Mouse;
 use super::InputEvent;
 
 /// A small event handler that wrap crossterm input and tick event. Each event
@@ -15,6 +17,8 @@ pub struct Events {
     _tx: tokio::sync::mpsc::Sender<InputEvent>,
     // To stop the loop
     stop_capture: Arc<AtomicBool>,
+    /// Held while the terminal is polled, the keys are not read while it is held elsewhere
+    polling: Arc<Mutex<()>>,
 }
 
 impl Events {
@@ -22,18 +26,33 @@ impl Events {
     pub fn new(tick_rate: Duration) -> Events {
         let (tx, rx) = tokio::sync::mpsc::channel(100);
         let stop_capture = Arc::new(AtomicBool::new(false));
+        let polling = Arc::new(Mutex::new(()));
 
         let event_tx = tx.clone();
         let event_stop_capture = stop_capture.clone();
+        let event_polling = polling.clone();
         tokio::spawn(async move {
             loop {
-                // poll for tick rate duration, if no event, sent tick event.
-                if crossterm::event::poll(tick_rate).unwrap() {
-                    if let crossterm::event::Event::Key(key) = crossterm::event::read().unwrap() {
-                        let key = Key::from(key);
-                        if let Err(err) = event_tx.send(InputEvent::Input(key)).await {
-                            error!("Oops!, {}", err);
-                        }
+                let input = {
+                    let _polling = event_polling.lock().await;
+                    // poll for tick rate duration, if no event, sent tick event.
+                    match crossterm::event::poll(tick_rate).unwrap() {
+                        true => match crossterm::event::read().unwrap() {
+                            crossterm::event::Event::Key(key) => {
+                                Some(InputEvent::Input(Key::from(key)))
+                            }
+                            crossterm::event::Event::Mouse(mouse) => {
+                                Mouse::from_event(mouse).map(InputEvent::Mouse)
+                            }
+                            crossterm::event::Event::Resize(..) => Some(InputEvent::Resize),
+                            _ => None,
+                        },
+                        false => None,
+                    }
+                };
+                if let Some(input) = input {
+                    if let Err(err) = event_tx.send(input).await {
+                        error!("Oops!, {}", err);
                     }
                 }
                 if let Err(err) = event_tx.send(InputEvent::Tick).await {
@@ -49,6 +68,7 @@ impl Events {
             rx,
             _tx: tx,
             stop_capture,
+            polling,
         }
     }
 
@@ -57,6 +77,12 @@ impl Events {
         self.rx.recv().await.unwrap_or(InputEvent::Tick)
     }
 
+    /// Stops reading the keys until the guard is dropped, so they reach the program the
+    /// terminal is handed to
+    pub async fn pause(&self) -> OwnedMutexGuard<()> {
+        self.polling.clone().lock_owned().await
+    }
+
     /// Close
     pub fn close(&mut self) {
         self.stop_capture.store(true, Ordering::Relaxed)
@@ -1,4 +1,5 @@
 use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
 use crossterm::event;
 
@@ -115,6 +116,81 @@ impl Display for Key {
     }
 }
 
+impl FromStr for Key {
+    type Err = String;
+
+    /// Reads a key written in the settings, such as `q`, `ctrl+c`, `alt+1`, `enter`,
+    /// `page-down` or `f5`, with or without the `<>` shown in the help
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let key = text.trim();
+        let key = key
+            .strip_prefix('<')
+            .and_then(|key| key.strip_suffix('>'))
+            .filter(|key| !key.is_empty())
+            .unwrap_or(key);
+        let unknown = || format!("Unknown key \"{}\"", text);
+
+        let (modifier, name) = match key.split_once('+') {
+            Some((modifier, name)) if !modifier.is_empty() && !name.is_empty() => {
+                (Some(modifier.to_lowercase()), name)
+            }
+            _ => (None, key),
+        };
+
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return char_key(modifier.as_deref(), c).ok_or_else(unknown);
+        }
+
+        let name = name
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .collect::<String>()
+            .to_lowercase();
+        if name == "space" {
+            return char_key(modifier.as_deref(), ' ').ok_or_else(unknown);
+        }
+        if modifier.is_some() {
+            return Err(unknown());
+        }
+
+        let key = match name.as_str() {
+            "enter" | "return" => Key::Enter,
+            "tab" => Key::Tab,
+            "backspace" => Key::Backspace,
+            "esc" | "escape" => Key::Esc,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "ins" | "insert" => Key::Ins,
+            "del" | "delete" => Key::Delete,
+            "home" => Key::Home,
+            "end" => Key::End,
+            "pageup" | "pgup" => Key::PageUp,
+            "pagedown" | "pgdn" => Key::PageDown,
+            _ => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if n <= 12 => Key::from_f(n),
+                _ => return Err(unknown()),
+            },
+        };
+
+        Ok(key)
+    }
+}
+
+/// A character typed with a modifier, `None` when the modifier is not supported
+fn char_key(modifier: Option<&str>, c: char) -> Option<Key> {
+    match modifier {
+        None => Some(Key::Char(c)),
+        Some("ctrl") => Some(Key::Ctrl(c.to_ascii_lowercase())),
+        Some("alt") => Some(Key::Alt(c)),
+        // The terminal sends the shifted character
+        Some("shift") => Some(Key::Char(c.to_ascii_uppercase())),
+        Some(_) => None,
+    }
+}
+
 impl From<event::KeyEvent> for Key {
     fn from(key_event: event::KeyEvent) -> Self {
         match key_event {
@@ -200,3 +276,59 @@ impl From<event::KeyEvent> for Key {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_read_back_from_their_names() {
+        let mut keys = vec![
+            Key::Enter,
+            Key::Tab,
+            Key::Backspace,
+            Key::Esc,
+            Key::Left,
+            Key::Right,
+            Key::Up,
+            Key::Down,
+            Key::Ins,
+            Key::Delete,
+            Key::Home,
+            Key::End,
+            Key::PageUp,
+            Key::PageDown,
+            Key::Char('q'),
+            Key::Char('Q'),
+            Key::Char(' '),
+            Key::Char('+'),
+            Key::Char('<'),
+            Key::Ctrl('c'),
+            Key::Ctrl(' '),
+            Key::Alt('1'),
+            Key::Alt(' '),
+            Key::Alt('+'),
+        ];
+        keys.extend((0..=12).map(Key::from_f));
+
+        for key in keys {
+            assert_eq!(key.to_string().parse(), Ok(key), "{}", key);
+        }
+    }
+
+    #[test]
+    fn reads_the_names_written_in_the_settings() {
+        assert_eq!("page-down".parse(), Ok(Key::PageDown));
+        assert_eq!("CTRL+C".parse(), Ok(Key::Ctrl('c')));
+        assert_eq!("shift+a".parse(), Ok(Key::Char('A')));
+        assert_eq!("alt+space".parse(), Ok(Key::Alt(' ')));
+        assert_eq!(" F5 ".parse(), Ok(Key::F5));
+
+        for name in ["", "f13", "ctrl+enter", "hyper+a", "enterr"] {
+            assert_eq!(
+                name.parse::<Key>(),
+                Err(format!("Unknown key \"{}\"", name))
+            );
+        }
+    }
+}
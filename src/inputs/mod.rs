@@ -1,11 +1,17 @@
 use self::key::Key;
+use self::mouse::Mouse;
 
 pub mod events;
 pub mod key;
+pub mod mouse;
 
 pub enum InputEvent {
     /// An input event occurred.
     Input(Key),
+    /// The mouse was clicked or scrolled
+    Mouse(Mouse),
+    /// The terminal was resized, it is drawn again
+    Resize,
     /// An tick event occurred.
     Tick,
 }
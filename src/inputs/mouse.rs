@@ -0,0 +1,35 @@
+use crossterm::event::{self, MouseButton, MouseEventKind};
+
+/// What the mouse did, the other events are not used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseKind {
+    /// The left button was pressed
+    Click,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// A mouse event, at a cell of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse {
+    pub kind: MouseKind,
+    pub column: u16,
+    pub row: u16,
+}
+
+impl Mouse {
+    pub fn from_event(mouse_event: event::MouseEvent) -> Option<Self> {
+        let kind = match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) => MouseKind::Click,
+            MouseEventKind::ScrollUp => MouseKind::ScrollUp,
+            MouseEventKind::ScrollDown => MouseKind::ScrollDown,
+            _ => return None,
+        };
+
+        Some(Self {
+            kind,
+            column: mouse_event.column,
+            row: mouse_event.row,
+        })
+    }
+}
@@ -1,27 +1,25 @@
-use std::io::{Error, ErrorKind};
-use std::time::Duration;
-use std::{process::Stdio, sync::Arc};
+use std::io::Error;
+use std::sync::Arc;
 
-use log::{debug, error, info, warn};
-use similar::{ChangeTag, TextDiff};
-use tokio::fs::{self, File};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::Command;
-use tokio::time::{timeout, Instant};
+use log::{debug, error, info};
+use tokio::fs;
 
 use super::IoEvent;
-use crate::app::{get_list_index, App, Data};
-
-const DB_PATH: &str = "./data.json";
+use crate::app::diff_cache::TestDiff;
+use crate::app::{get_list_index, App};
+use crate::report::{html, Report, HTML_REPORT};
+use crate::runner::{build, EventBus, Job};
+use crate::suite::{Results, TestStatus, HISTORY_OUTPUTS_PATH, HISTORY_PATH, RESULTS_PATH};
 
 /// In the IO thread, we handle IO event without blocking the UI thread
 pub struct IoAsyncHandler {
     app: Arc<tokio::sync::Mutex<App>>,
+    events: EventBus,
 }
 
 impl IoAsyncHandler {
-    pub fn new(app: Arc<tokio::sync::Mutex<App>>) -> Self {
-        Self { app }
+    pub fn new(app: Arc<tokio::sync::Mutex<App>>, events: EventBus) -> Self {
+        Self { app, events }
     }
 
     /// We could be async here
@@ -35,11 +33,15 @@ impl IoAsyncHandler {
             IoEvent::LoadChecksyle => self.load_cs().await,
             IoEvent::Make => self.run_make().await,
             IoEvent::UpdateRef => self.update_ref().await,
+            IoEvent::WriteReport => self.write_report().await,
+            IoEvent::RecordRun => self.record_run().await,
             // IoEvent::SendVMChecker => self.send_vmchecker().await,
             // IoEvent::LoadVMChecker => self.load_vmchecker().await,
         };
 
-        self.update_ref().await.unwrap();
+        if let Err(Some(err)) = self.update_ref().await {
+            error!("Cannot load the ref of the selected test: {}", err);
+        }
 
         if let Err(Some(output)) = result {
             error!("Oops, something wrong happened: \n{}", output.to_string());
@@ -91,21 +93,21 @@ impl IoAsyncHandler {
                     .header("cookie", &cookie)
                     .send().await {
             Ok(res) => {
-    			let v: Value = serde_json::from_str(&res.text().await.unwrap()).unwrap();
-
-    			let mut app = self.app.lock().await;
-
-    			match v.get(5) {
-    				Some(output) => {
-    					app.vmchecker_out.clear();
-    					app.vmchecker_out.push_str(&output["Execuția testelor (stdout)"].to_string());
-    				},
-    				None => {
-    					app.vmchecker_out.clear();
-    					app.vmchecker_out.push_str(&v[2].to_string());
-    				},
-    			}
-    		},
+                let v: Value = serde_json::from_str(&res.text().await.unwrap()).unwrap();
+
+                let mut app = self.app.lock().await;
+
+                match v.get(5) {
+                    Some(output) => {
+                        app.vmchecker_out.clear();
+                        app.vmchecker_out.push_str(&output["Execuția testelor (stdout)"].to_string());
+                    },
+                    None => {
+                        app.vmchecker_out.clear();
+                        app.vmchecker_out.push_str(&v[2].to_string());
+                    },
+                }
+            },
             Err(err) => {
                 return Err(Some(Error::new(ErrorKind::Other, err.to_string())));
             },
@@ -256,94 +258,162 @@ impl IoAsyncHandler {
     }
     */
 
+    /// Shows the diff of the selected test, computing it without holding the app unless
+    /// it is cached
     async fn update_ref(&self) -> Result<(), Option<Error>> {
-        let mut app = self.app.lock().await;
+        loop {
+            let mut app = self.app.lock().await;
 
-        let index = app.test_list_state.selected().unwrap();
-        let (test_index, exec_index) = get_list_index(&app.test_list, index);
+            let index = app.test_list_state.selected().unwrap();
+            let test = get_list_index(&app.runner.suite.tests, index);
+            let (test_index, exec_index) = test;
+            if app.diff_test == Some(test) && app.diff_cache.contains(test) {
+                return Ok(());
+            }
 
-        app.current_ref = fs::read_to_string(format!(
-            "{}ref/{:02}-{}.ref",
-            app.test_path, app.test_list[exec_index][test_index].id, app.exec_name[exec_index]
-        ))
-        .await?;
-
-        app.diff =
-            TextDiff::from_lines(&app.current_ref, &app.test_list[exec_index][test_index].log)
-                .iter_all_changes()
-                .map(|item| {
-                    let sign = match item.tag() {
-                        ChangeTag::Delete => "-",
-                        ChangeTag::Insert => "+",
-                        ChangeTag::Equal => " ",
-                    };
-
-                    match item.missing_newline() {
-                        true => (sign, format!("{}", item)),
-                        false => (sign, format!("{}⏎", item)),
-                    }
-                })
-                .collect();
+            let id = app.runner.suite.tests[exec_index][test_index].id;
+            if app.diff_test != Some(test) {
+                // Not every test has an input
+                app.current_input = fs::read_to_string(format!(
+                    "{}input/{:02}-{}.in",
+                    app.runner.suite.test_path, id, app.runner.suite.exec_name[exec_index]
+                ))
+                .await
+                .unwrap_or_default();
+            }
 
-        Ok(())
-    }
+            if let Some(diff) = app.diff_cache.get(test) {
+                app.show_diff(test, diff);
+                return Ok(());
+            }
+            // Another IO thread is computing it
+            if !app.diff_cache.start(test) {
+                return Ok(());
+            }
 
-    async fn save_data(&mut self, data: Data) -> Result<(), Option<Error>> {
-        debug!("Saving data");
-        tokio::fs::write(DB_PATH, serde_json::to_string_pretty(&data).unwrap()).await?;
-        Ok(())
+            let path = format!(
+                "{}ref/{:02}-{}.ref",
+                app.runner.suite.test_path, id, app.runner.suite.exec_name[exec_index]
+            );
+            let log = app.runner.suite.tests[exec_index][test_index].log.clone();
+            drop(app);
+
+            let diff = match fs::read_to_string(path).await {
+                Ok(reference) => {
+                    tokio::task::spawn_blocking(move || TestDiff::new(&reference, &log))
+                        .await
+                        .map_err(Error::other)
+                }
+                Err(err) => Err(err),
+            };
+
+            let mut app = self.app.lock().await;
+            let diff = match diff {
+                Ok(diff) => diff,
+                Err(err) => {
+                    app.diff_cache.cancel(test);
+                    return Err(Some(err));
+                }
+            };
+
+            let (diff, outdated) = match app.diff_cache.finish(test, diff) {
+                Ok(diff) => (diff, false),
+                Err(diff) => (diff, true),
+            };
+            let selected = app
+                .test_list_state
+                .selected()
+                .map(|index| get_list_index(&app.runner.suite.tests, index));
+            if selected == Some(test) {
+                app.show_diff(test, diff);
+            }
+
+            // The output changed while the diff was computed. A running test is diffed
+            // again on a later tick, one that finished since has its whole output now.
+            if outdated
+                && !app.runner.suite.tests[exec_index][test_index]
+                    .status
+                    .is_active()
+            {
+                continue;
+            }
+
+            return Ok(());
+        }
     }
 
-    async fn load_cs(&mut self) -> Result<(), Option<Error>> {
-        info!("Running checkstyle");
+    /// Reads the files and diffs the outputs without holding the app
+    async fn write_report(&self) -> Result<(), Option<Error>> {
+        let mut report = Report::snapshot(&self.app.lock().await.runner);
 
-        let mut app = self.app.lock().await;
+        let path = format!("{}{}", report.test_path, HTML_REPORT);
+        let html = tokio::task::spawn_blocking(move || {
+            report.read_files();
+            html::render(&report)
+        })
+        .await
+        .map_err(Error::other)?;
 
-        let mut cs = Command::new(format!("{}/cs/cs.sh", app.test_path));
-        cs.arg(".");
+        fs::write(&path, html).await?;
+        info!("Report written to {}", path);
 
-        let output = cs.output().await?.stdout;
+        Ok(())
+    }
 
-        app.checkstyle.clear();
-        app.checkstyle
-            .push_str(std::str::from_utf8(&output).unwrap());
+    /// Adds the results to the history, writing the files without holding the app
+    async fn record_run(&self) -> Result<(), Option<Error>> {
+        let run = self.app.lock().await.runner.history_run();
+        let run = tokio::task::spawn_blocking(move || run.store(HISTORY_OUTPUTS_PATH))
+            .await
+            .map_err(Error::other)?;
+
+        let history = {
+            let mut app = self.app.lock().await;
+            app.history.push(run);
+            // A test may have been run again while the run was stored
+            let suite = &app.runner.suite;
+            let showing_last_run =
+                app.history
+                    .is_last(&suite.exec_name, &suite.tests, &suite.scoring);
+            app.showing_last_run = showing_last_run;
+            app.history.clone()
+        };
+        let saved =
+            tokio::task::spawn_blocking(move || history.save(HISTORY_PATH, HISTORY_OUTPUTS_PATH))
+                .await
+                .map_err(Error::other)?;
+        if let Err(err) = saved {
+            error!("{:#}", err);
+        }
 
-        let mut out_file = File::create(format!("{}checkstyle.txt", app.test_path)).await?;
+        Ok(())
+    }
 
-        out_file.write_all(&output).await?;
+    async fn save_data(&mut self, results: Results) -> Result<(), Option<Error>> {
+        debug!("Saving results");
 
-        let mut new_error = vec![0, 0, 0];
+        // Tests left out by a filter keep their previous results
+        let mut stored = Results::load(RESULTS_PATH).unwrap_or_default();
+        stored.update(results);
 
-        app.checkstyle
-        .lines().for_each(|line| {
-            match line {
-                _ if line.contains("CHECK") => new_error[0] += 1,
-                _ if line.contains("WARNING") => new_error[1] += 1,
-                _ if line.contains("ERROR") => new_error[2] += 1,
-                _ => (),
-            };
-        });
+        tokio::fs::write(RESULTS_PATH, serde_json::to_string_pretty(&stored).unwrap()).await?;
+        Ok(())
+    }
 
-        app.errors = new_error;
+    async fn load_cs(&mut self) -> Result<(), Option<Error>> {
+        info!("Running checkstyle");
+
+        let mut app = self.app.lock().await;
+
+        app.runner.run_checkstyle().await?;
 
         Ok(())
     }
 
     async fn run_make(&self) -> Result<(), Option<Error>> {
-        info!("Running makefile");
-        let mut make = Command::new("make");
-        let make_run = make.arg("build");
-        let res = make_run.output().await?;
-
-        if let Some(code) = res.status.code() {
-            if code != 0 {
-                return Err(Some(Error::new(
-                    ErrorKind::Other,
-                    std::str::from_utf8(&res.stderr).unwrap(),
-                )));
-            }
-            info!("\n{}", String::from_utf8(res.stdout).unwrap());
-        }
+        build(&self.events)
+            .await
+            .map_err(|err| Some(Error::other(err.to_string())))?;
 
         Ok(())
     }
@@ -360,13 +430,9 @@ impl IoAsyncHandler {
                 debug!("Waiting on mutex");
                 let mut app = copy.lock().await;
 
-                let (test_index, exec_index) = get_list_index(&app.test_list, index);
+                let (test_index, exec_index) = get_list_index(&app.runner.suite.tests, index);
 
-                app.test_list[exec_index][test_index].status.clear();
-                app.test_list[exec_index][test_index]
-                    .status
-                    .push_str("STARTING");
-                app.dispatch(IoEvent::RunTest(test_index, exec_index)).await;
+                app.queue_test(test_index, exec_index).await;
             });
 
             threads.push(thread);
@@ -376,6 +442,8 @@ impl IoAsyncHandler {
             thread.await.unwrap();
         }
 
+        self.app.lock().await.pending_report = true;
+
         Ok(())
     }
 
@@ -393,11 +461,7 @@ impl IoAsyncHandler {
 
                 let (test_index, exec_index) = index;
 
-                app.test_list[exec_index][test_index].status.clear();
-                app.test_list[exec_index][test_index]
-                    .status
-                    .push_str("STARTING");
-                app.dispatch(IoEvent::RunTest(test_index, exec_index)).await;
+                app.queue_test(test_index, exec_index).await;
             });
 
             threads.push(thread);
@@ -410,251 +474,32 @@ impl IoAsyncHandler {
         Ok(())
     }
 
-    /**
-     * Runs a single test, by opening or creating an output file,
-     * adding the input of the program to the stdin of the executable and
-     * by comparing the ref file with the program's output.
-     *
-     * Oh god, this is a mess but it is working
-     */
+    /// Runs a single test, without holding the app while the executable runs
     async fn run_test(&self, index: usize, exec: usize) -> Result<(), Option<Error>> {
-        let mut app = self.app.lock().await;
-
-        let app_name = String::from(&app.exec_name[exec]);
-
-        let mut out_file = File::create(format!(
-            "{}output/{:02}-{}.out",
-            app.test_path, index, app_name
-        ))
-        .await?;
-
-        let valgrind = app.valgrind_enabled;
-
-        let ref_prom = fs::read(format!(
-            "{}ref/{:02}-{}.ref",
-            app.test_path, index, app_name
-        ));
-
-        let ref_file = match ref_prom.await {
-            Ok(f1) => f1,
-            Err(a) => {
-                error!(
-                    "Cannot find {}",
-                    format!("{}input/{:02}-{}.ref", app.test_path, index, app_name)
-                );
-
-                let current_test = &mut app.test_list[exec][index];
-                current_test.status.clear();
-                current_test.status.push_str("ERROR");
-                return Err(Some(a));
-            }
+        let job = {
+            let mut app = self.app.lock().await;
+            let job = Job::new(
+                &app.runner.suite.test_path,
+                &app.runner.suite.exec_name[exec],
+                &app.runner.suite.tests[exec][index],
+                app.runner.suite.valgrind_enabled,
+            );
+
+            let current_test = &mut app.runner.suite.tests[exec][index];
+            current_test.status = TestStatus::Running;
+            current_test.passed_output = false;
+
+            job
         };
 
-        let mut binding: Command;
-        if valgrind {
-            binding = Command::new("valgrind");
-            binding
-                .arg(format!(
-                    "--log-file={}output/{:02}-{}.valgrind",
-                    app.test_path, index, app_name
-                ))
-                .arg("--leak-check=full")
-                .arg("--track-origins=yes")
-                .arg("--show-leak-kinds=all")
-                .arg("--error-exitcode=69")
-                .arg(format!("./{}", app_name));
-        } else {
-            binding = Command::new(format!("./{}", app_name));
-        }
-
-        let current_test = &mut app.test_list[exec][index];
-        current_test.status.clear();
-        current_test.status.push_str("RUNNING");
-        let timelimit = current_test.timeout;
-
-        info!(
-            "Running {} with test number {} with status {}",
-            app_name, index, current_test.status
-        );
-        let in_file = std::fs::File::open(format!(
-            "{}input/{:02}-{}.in",
-            app.test_path, index, app_name
-        ))?;
-        drop(app);
-
-        let run = binding.stdin(in_file).stdout(Stdio::piped());
-
-        debug!("Executing {:?}", run);
-        match run.spawn() {
-            Ok(mut child) => {
-                debug!("{:?}", child);
-
-                let mut log = String::new();
-                let mut res = String::new();
-                let start = Instant::now();
-
-                debug!("Finished input, waiting for stdout");
-
-                if let Some(ref mut stdout) = child.stdout {
-                    let mut lines = BufReader::new(stdout).lines();
-
-                    loop {
-                        if let Ok(res_line) =
-                            timeout(Duration::from_millis(timelimit), lines.next_line()).await
-                        {
-                            if let Ok(Some(line)) = res_line {
-                                let l: String = format!("{}\n", line);
-                                debug!("file_contains {}", l);
-                                log.push_str(&l);
-                            } else {
-                                debug!("Finished reading from stdout");
-                                break;
-                            }
-
-                            if start.elapsed().as_millis() > timelimit as u128 {
-                                warn!("timeout");
-                                res.push_str("TIMEOUT");
-
-                                let mut app = self.app.lock().await;
-                                let current_test = &mut app.test_list[exec][index];
-                                current_test.status.clear();
-                                current_test.status.push_str(&res);
-                                current_test.log.clear();
-
-                                if valgrind {
-                                    current_test.time_valgrind = start.elapsed().as_secs_f64();
-                                } else {
-                                    current_test.time_normal = start.elapsed().as_secs_f64();
-                                }
-
-                                child.kill().await?;
-
-                                return Ok(());
-                            }
-                        } else {
-                            warn!("timeout");
-                            res.push_str("TIMEOUT");
-
-                            let mut app = self.app.lock().await;
-                            let current_test = &mut app.test_list[exec][index];
-                            current_test.status.clear();
-                            current_test.status.push_str(&res);
-                            current_test.log.clear();
-
-                            if valgrind {
-                                current_test.time_valgrind = start.elapsed().as_secs_f64();
-                            } else {
-                                current_test.time_normal = start.elapsed().as_secs_f64();
-                            }
-
-                            child.kill().await?;
-
-                            return Ok(());
-                        }
-                    }
-                } else {
-                    warn!("I think it crashed");
-                }
-
-                debug!("time here is {}", start.elapsed().as_secs_f64());
-
-                if let Ok(out) = child.wait_with_output().await {
-                    debug!("exit status {:?}", out.status.code());
-                    if out.status.code().is_none() {
-                        let runtime = start.elapsed().as_secs_f64();
-
-                        log.push_str(&out.status.to_string().split_off(8));
-                        res.push_str("CRASHED");
-
-                        let mut app = self.app.lock().await;
-                        let current_test = &mut app.test_list[exec][index];
-
-                        current_test.status.clear();
-                        current_test.status.push_str(&res);
-                        current_test.log.clear();
-                        current_test.log.push_str(&log);
+        let outcome = job.run(&self.events).await;
+        debug!("time={:5}", outcome.time);
 
-                        if valgrind {
-                            current_test.time_valgrind = runtime;
-                        } else {
-                            current_test.time_normal = runtime;
-                        }
-
-                        app.unwritten_data = true;
-                        return Ok(());
-                    } else if let Some(69) = out.status.code() {
-                        let runtime = start.elapsed().as_secs_f64();
-
-                        log.push_str("Check output folder for valgrind errors");
-                        res.push_str("MEMLEAKS");
-
-                        let mut app = self.app.lock().await;
-                        let current_test = &mut app.test_list[exec][index];
-
-                        current_test.status.clear();
-                        current_test.status.push_str(&res);
-                        current_test.log.clear();
-                        current_test.log.push_str(&log);
-
-                        if valgrind {
-                            current_test.time_valgrind = runtime;
-                        } else {
-                            current_test.time_normal = runtime;
-                        }
-
-                        app.unwritten_data = true;
-                        return Ok(());
-                    }
-                } else {
-                    error!("Oops");
-                }
-
-                let correct: bool = if log == std::str::from_utf8(&ref_file).unwrap() {
-                    true
-                } else {
-                    res.push('0');
-                    false
-                };
-
-                let runtime = start.elapsed().as_secs_f64();
-                debug!("time={:5}", runtime);
-
-                out_file.write_all(log.as_bytes()).await?;
-
-                let mut app = self.app.lock().await;
-                let current_test = &mut app.test_list[exec][index];
-
-                if valgrind {
-                    current_test.time_valgrind = runtime;
-                } else {
-                    current_test.time_normal = runtime;
-                }
-
-                if correct {
-                    res.push_str(&current_test.test_score.to_string());
-                }
-
-                current_test.status.clear();
-                current_test.status.push_str(&res);
-                current_test.log.clear();
-                current_test.log.push_str(&log);
-
-                app.unwritten_data = true;
-            }
-            Err(error) => {
-                let mut app = self.app.lock().await;
-                let current_test = &mut app.test_list[exec][index];
-
-                current_test.status.clear();
-                current_test.status.push_str("ERROR");
-
-                current_test.log.clear();
-                current_test.log.push_str(&error.to_string());
-
-                app.unwritten_data = true;
-                warn!("{:?}", error);
-            }
-        }
+        let mut app = self.app.lock().await;
+        outcome.apply(&mut app.runner.suite.tests[exec][index]);
+        app.showing_last_run = false;
+        app.diff_cache.invalidate((index, exec));
+        app.unwritten_data = true;
 
         Ok(())
     }
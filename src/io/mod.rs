@@ -1,4 +1,4 @@
-use crate::app::Data;
+use crate::suite::Results;
 
 pub mod handler;
 
@@ -8,10 +8,12 @@ pub enum IoEvent {
     RunTest(usize, usize),
     RunAll(usize),
     RunFailed(Vec<(usize, usize)>),
-    SaveData(Data),
+    SaveData(Results),
     LoadChecksyle,
     Make,
     UpdateRef,
+    WriteReport,
+    RecordRun,
     // SendVMChecker,
     // LoadVMChecker,
 }
@@ -1,138 +1,134 @@
-// use std::io::{BufRead, BufReader, Write};
-use std::process::{exit, Stdio};
-use std::time::{Duration, Instant};
-use tokio::fs::{self, File};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::process::Command;
 
-use hw_checker::app::{App, Test};
-use tokio::time::timeout;
+use hw_checker::runner::{Event, RunOptions, Runner};
+use hw_checker::suite::{format_points, Scoring, Test, TestStatus};
 
-pub async fn run_tests(mut app: App) {
-    let mut score: isize = 0;
+/// Set when a report is written to stdout, so the progress goes to stderr
+static PROGRESS_TO_STDERR: AtomicBool = AtomicBool::new(false);
 
-    println!("Running makefile");
-    let mut make = Command::new("make");
-    let make_run = make.arg("build");
-    let res = match make_run.output().await {
-        Ok(out) => out,
-        Err(err) => {
-            println!("Error {:?}", err);
-            exit(1);
-        }
+/// Prints a line of progress
+macro_rules! say {
+    () => {
+        say!("")
     };
-
-    if let Some(code) = res.status.code() {
-        if code != 0 {
-            println!("Makefile error or makefile not found, stopping");
-            println!("{}", String::from_utf8(res.stdout).unwrap());
-            println!("{}", String::from_utf8(res.stderr).unwrap());
-            return;
+    ($($arg:tt)*) => {
+        if PROGRESS_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
         }
-        println!(
-            "{}",
-            match String::from_utf8(res.stdout) {
-                Ok(stdout) => stdout,
-                Err(err) => {
-                    println!("Error {:?}", err);
-                    exit(1);
-                }
-            }
-        );
-    }
+    };
+}
 
-    for (i, test_list) in app.test_list.iter().enumerate() {
-        let app_name = &app.exec_name[i];
+/// Sends the progress to stderr, keeping stdout for a report
+pub fn progress_to_stderr(enabled: bool) {
+    PROGRESS_TO_STDERR.store(enabled, Ordering::Relaxed);
+}
 
-        println!();
-        println!("==== {app_name} ====");
+/// Runs every test and prints the results.
+///
+/// Returns false when the homework cannot be built.
+pub async fn run_tests(runner: &mut Runner, options: &RunOptions) -> bool {
+    for warning in runner.suite.warnings.iter() {
+        say!("Warning: {}", warning);
+    }
 
-        for (index, test) in test_list.iter().enumerate() {
-            match run_test(test, index, app_name, &app.test_path).await {
-                Ok(amount) => score += amount as isize,
-                Err(err) => println!("Error {:?}", err),
-            };
+    say!("Running makefile");
+    match runner.build().await {
+        Ok(output) => say!("{}", output),
+        Err(err) => {
+            say!("{}", err);
+            return false;
         }
     }
 
-    println!();
-    println!("Running checkstyle");
+    // The runner holds the tests while they run
+    let exec_name = runner.suite.exec_name.clone();
+    let scoring = runner.suite.scoring.clone();
+    runner
+        .run(options, |event| match event {
+            Event::Executable(exec) => {
+                say!();
+                say!("==== {} ====", exec_name[exec]);
+            }
+            Event::Finished { exec, test, .. } => print_test(&exec_name[exec], test, &scoring),
+        })
+        .await;
 
-    let mut cs = Command::new(format!("{}/cs/cs.sh", app.test_path));
-    cs.arg(".");
+    run_checkstyle(runner).await;
 
-    let output = match cs.output().await {
-        Ok(res) => res.stdout,
-        Err(err) => {
-            println!("Error {:?}", err);
-            exit(1);
-        }
-    };
+    run_make_clean().await;
 
-    app.checkstyle.clear();
-    app.checkstyle.push_str(match std::str::from_utf8(&output) {
-        Ok(res) => res,
-        Err(err) => {
-            println!("Error {:?}", err);
-            exit(1);
-        }
-    });
+    print_score(runner);
 
-    let mut out_file = match File::create(format!("{}checkstyle.txt", app.test_path)).await {
-        Ok(res) => res,
-        Err(err) => {
-            println!("Error {:?}", err);
-            exit(1);
-        }
-    };
+    true
+}
 
-    match out_file.write_all(&output).await {
-        Ok(_) => {}
-        Err(err) => {
-            println!("Error {:?}", err);
-            exit(1);
-        }
+/// Prints the outcome of a test, with the points it got
+fn print_test(exec: &str, test: &Test, scoring: &Scoring) {
+    let label = match test.status {
+        TestStatus::Passed { .. } => "PASSED",
+        TestStatus::Failed => "FAILED",
+        TestStatus::Timeout => "TIMEOUT",
+        TestStatus::Crashed { .. } => "CRASHED",
+        TestStatus::MemLeaks => "MEMLEAKS",
+        _ => "ERROR",
     };
 
-    if app.checkstyle.is_empty() {
-        println!("No coding style errors found");
+    say!(
+        "Running {} test {}\t\tTime: {:.5}",
+        exec,
+        test.id,
+        test.time_valgrind.max(test.time_normal)
+    );
+    say!(
+        "Test {:02}{}{}: {}/{}",
+        test.id,
+        ".".repeat(33 - label.len()),
+        label,
+        format_points(scoring.test_points(test)),
+        test.test_score
+    );
+    if let TestStatus::Error { reason } = &test.status {
+        say!("Error: {}", reason);
+    }
+}
 
-        println!("+10 points: Checkstyle");
-        score += 10;
-    } else {
-        println!("{}", app.checkstyle);
-        println!(
-            "Check {}chekstyle.txt for all the {} errors\n",
-            app.test_path,
-            app.checkstyle.lines().count()
+/// Prints the subtotals and the final score, as shown by the interactive checker
+pub fn print_score(runner: &Runner) {
+    let breakdown = runner.score_breakdown();
+
+    say!();
+    say!("==== Score ====");
+    for row in breakdown.rows() {
+        let name = format!("{}{}", "  ".repeat(row.depth), row.name);
+        say!(
+            "{:<30}{:>10}",
+            name,
+            format!("{}/{}", format_points(row.score), format_points(row.max))
         );
-
-        let mut errors = vec![0, 0, 0];
-
-        app.checkstyle.lines().for_each(|line| {
-            match line {
-                _ if line.contains("CHECK") => errors[0] += 1,
-                _ if line.contains("WARNING") => errors[1] += 1,
-                _ if line.contains("ERROR") => errors[2] += 1,
-                _ => (),
-            };
-        });
-
-        if errors.iter().sum::<isize>() > 0 {
-            println!("Found {} errors, reduce them to 0 to get 10 points", errors.iter().sum::<isize>());
-        } else {
-            println!("+10 points: Checkstyle");
-            score += 10;
-        }
     }
+    say!(
+        "Total: {}/{}\n",
+        format_points(breakdown.total),
+        format_points(breakdown.max)
+    );
+}
 
-    println!("Running make clean");
+/// Runs `make clean`, printing any error
+pub async fn run_make_clean() {
+    say!("Running make clean");
     let mut make = Command::new("make");
     make.arg("clean");
+    if PROGRESS_TO_STDERR.load(Ordering::Relaxed) {
+        make.stdout(std::io::stderr());
+    }
     let mut child = match make.spawn() {
         Ok(chld) => chld,
         Err(err) => {
-            println!("Error {:?}", err);
+            say!("Error {:?}", err);
             exit(1);
         }
     };
@@ -140,155 +136,42 @@ pub async fn run_tests(mut app: App) {
     match child.wait().await {
         Ok(_) => {}
         Err(err) => {
-            println!("Error {:?}", err);
+            say!("Error {:?}", err);
         }
     };
-
-    println!("Total: {score}\n");
 }
 
-async fn run_test(
-    test: &Test,
-    index: usize,
-    app_name: &String,
-    path: &String,
-) -> Result<usize, std::io::Error> {
-    let mut run: Command;
-    if index < 13 {
-        run = Command::new("valgrind");
-        run.arg(format!(
-            "--log-file={}output/{:02}-{}.valgrind",
-            path, index, app_name
-        ))
-        .arg("--leak-check=full")
-        .arg("--track-origins=yes")
-        .arg("--show-leak-kinds=all")
-        .arg("--error-exitcode=69")
-        .arg(format!("./{}", app_name));
-    } else {
-        run = Command::new(format!("./{}", app_name));
-    }
-
-    print!("Running {app_name} test {index}");
-
-    let mut out_file =
-        File::create(format!("{}output/{:02}-{}.out", path, index, app_name)).await?;
-
-    let ref_file = fs::read(format!("{}ref/{:02}-{}.ref", path, index, app_name)).await?;
-
-    let in_file = std::fs::File::open(format!("{}input/{:02}-{}.in", path, index, app_name))?;
-
-    run.stdin(in_file).stdout(Stdio::piped());
-    if let Ok(mut child) = run.spawn() {
-        let mut log_file = String::new();
-        let start = Instant::now();
-
-        if let Some(ref mut stdout) = child.stdout {
-            let mut lines = BufReader::new(stdout).lines();
-
-            loop {
-                let time_left = test.timeout as u128 - start.elapsed().as_millis();
-                if let Ok(res) =
-                    timeout(Duration::from_millis(time_left as u64), lines.next_line()).await
-                {
-                    if let Ok(Some(line)) = res {
-                        let l: String = format!("{}\n", line);
-                        log_file.push_str(&l);
-                    } else {
-                        break;
-                    }
-
-                    if start.elapsed().as_millis() > test.timeout as u128 {
-                        println!("\t\tTime: {:.5}", start.elapsed().as_secs_f64());
-                        println!(
-                            "Test {index:02}{}TIMEOUT: 0/{}",
-                            ".".repeat(26),
-                            test.test_score
-                        );
-
-                        if let Err(err) = child.kill().await {
-                            println!("ERROR: Can't kill child: {:?}", err);
-                        }
-
-                        return Ok(0);
-                    }
-                } else {
-                    println!("\t\tTime: {:.5}", start.elapsed().as_secs_f64());
-                    println!(
-                        "Test {index:02}{}TIMEOUT: 0/{}",
-                        ".".repeat(26),
-                        test.test_score
-                    );
-
-                    if let Err(err) = child.kill().await {
-                        println!("ERROR: Can't kill child: {:?}", err);
-                    }
-
-                    return Ok(0);
-                }
-            }
-        } else {
-            eprintln!("Broken pipe");
-        }
-
-        if let Ok(out) = child.wait_with_output().await {
-            if out.status.code().is_none() {
-                println!("\t\tTime: {:.5}", start.elapsed().as_secs_f64());
-                println!(
-                    "Test {index:02}{}CRASHED: 0/{}",
-                    ".".repeat(26),
-                    test.test_score
-                );
-
-                log_file.push_str("Crashed\n");
-                out_file.write_all(log_file.as_bytes()).await?;
-
-                return Ok(0);
-            } else if let Some(69) = out.status.code() {
-                println!("\t\tTime: {:.5}", start.elapsed().as_secs_f64());
-                println!(
-                    "Test {index:02}{}MEMLEAKS: 0/{}",
-                    ".".repeat(25),
-                    test.test_score
-                );
-                
-                log_file.push_str("MEMLEAKS: Check .valgrind file for memory leak info\n");
-                out_file.write_all(log_file.as_bytes()).await?;
-
-                return Ok(0);
-            }
-        } else {
-            eprintln!("Cannot wait for child");
-        }
+/// Runs the coding style checker and prints its findings
+pub async fn run_checkstyle(runner: &mut Runner) {
+    say!();
+    say!("Running checkstyle");
 
-        println!("\t\tTime: {:.5}", start.elapsed().as_secs_f64());
+    if let Err(err) = runner.run_checkstyle().await {
+        say!("Error {:?}", err);
+        exit(1);
+    }
 
-        out_file.write_all(log_file.as_bytes()).await?;
-        if log_file
-            == match std::str::from_utf8(&ref_file) {
-                Ok(res) => res,
-                Err(err) => {
-                    println!("Error {:?}", err);
-                    exit(1);
-                }
-            }
-        {
-            println!(
-                "Test {index:02}{}PASSED: {}/{}",
-                ".".repeat(27),
-                test.test_score,
-                test.test_score
-            );
+    let checkstyle = &runner.checkstyle;
+    let rule = &runner.suite.scoring.checkstyle;
+    let found = checkstyle.problems();
 
-            return Ok(test.test_score);
-        } else {
-            println!(
-                "Test {index:02}{}FAILED: 0/{}",
-                ".".repeat(27),
-                test.test_score
-            );
-        }
+    if checkstyle.output.is_empty() {
+        say!("No coding style errors found");
+    } else {
+        say!("{}", checkstyle.output);
+        say!(
+            "Check {}chekstyle.txt for all the {} errors\n",
+            runner.suite.test_path,
+            checkstyle.output.lines().count()
+        );
     }
 
-    Ok(0)
+    if found > rule.max_errors {
+        say!(
+            "Found {} errors, reduce them to {} to get {} points",
+            found,
+            rule.max_errors,
+            format_points(rule.points)
+        );
+    }
 }
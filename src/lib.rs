@@ -1,4 +1,5 @@
 use std::io::stdout;
+use std::panic;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -7,6 +8,7 @@ use eyre::Result;
 use inputs::events::Events;
 use inputs::InputEvent;
 use io::IoEvent;
+use log::{error, warn};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
@@ -15,17 +17,22 @@ use crate::app::ui;
 pub mod app;
 pub mod inputs;
 pub mod io;
+pub mod report;
+pub mod runner;
+pub mod settings;
+pub mod suite;
 
 pub async fn start_ui(app: &Arc<tokio::sync::Mutex<App>>) -> Result<()> {
+    // Give the terminal back before printing the panic, otherwise it stays in raw mode
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+    }));
+
     // Configure Crossterm backend for tui
-    let mut stdout = stdout();
-    crossterm::terminal::enable_raw_mode()?;
-    crossterm::execute!(
-        stdout,
-        crossterm::terminal::EnterAlternateScreen,
-        crossterm::event::EnableMouseCapture
-    )?;
-    let backend = CrosstermBackend::new(stdout);
+    enter_terminal()?;
+    let backend = CrosstermBackend::new(stdout());
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
     terminal.hide_cursor()?;
@@ -38,8 +45,12 @@ pub async fn start_ui(app: &Arc<tokio::sync::Mutex<App>>) -> Result<()> {
     {
         let mut app = app.lock().await;
         app.dispatch(IoEvent::Initialize).await;
+        // The build can take a while, the first test is shown in the meantime
+        app.dispatch(IoEvent::UpdateRef).await;
     }
 
+    // The app is let go while a program has the terminal
+    let app_ui = Arc::clone(app);
     loop {
         let mut app = app.lock().await;
 
@@ -49,22 +60,66 @@ pub async fn start_ui(app: &Arc<tokio::sync::Mutex<App>>) -> Result<()> {
         // Handle inputs
         let result = match events.next().await {
             InputEvent::Input(key) => app.do_action(key).await,
+            InputEvent::Mouse(mouse) => app.do_mouse(mouse).await,
+            InputEvent::Resize => AppReturn::Continue,
             InputEvent::Tick => app.update_on_tick().await,
         };
-        // Check if we should exit
-        if result == AppReturn::Exit {
-            events.close();
-            break;
+        match result {
+            AppReturn::Exit => {
+                events.close();
+                break;
+            }
+            AppReturn::Continue => {}
+            AppReturn::Open(command) => {
+                // The IO threads keep running the tests meanwhile
+                drop(app);
+                let paused = events.pause().await;
+                restore_terminal()?;
+
+                match command.run().await {
+                    Ok(status) if !status.success() => warn!("{} exited with {}", command, status),
+                    Ok(_) => {}
+                    Err(err) => error!("{}", err),
+                }
+
+                enter_terminal()?;
+                terminal.clear()?;
+                terminal.hide_cursor()?;
+                drop(paused);
+
+                // The files may have been changed
+                app_ui.lock().await.refresh_diff().await;
+            }
         }
     }
 
     // Restore the terminal and close application
     // terminal.clear()?;
     terminal.show_cursor()?;
+    restore_terminal()?;
+
+    Ok(())
+}
+
+/// Enables raw mode and mouse capture and enters the alternate screen
+fn enter_terminal() -> Result<()> {
+    crossterm::terminal::enable_raw_mode()?;
+    crossterm::execute!(
+        stdout(),
+        crossterm::terminal::EnterAlternateScreen,
+        crossterm::event::EnableMouseCapture
+    )?;
+
+    Ok(())
+}
+
+/// Leaves the alternate screen and disables raw mode and mouse capture
+pub fn restore_terminal() -> Result<()> {
     crossterm::execute!(
-        terminal.backend_mut(),
+        stdout(),
         crossterm::terminal::LeaveAlternateScreen,
-        crossterm::event::DisableMouseCapture
+        crossterm::event::DisableMouseCapture,
+        crossterm::cursor::Show
     )?;
     crossterm::terminal::disable_raw_mode()?;
 
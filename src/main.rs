@@ -1,15 +1,28 @@
-use std::{env, sync::Arc, thread::available_parallelism, time::Duration};
+use std::process::exit;
+use std::{env, sync::Arc, time::Duration};
 
-use hw_checker::app::App;
+use clap::Parser;
+use eyre::Result;
+use hw_checker::app::{logs, App};
 use hw_checker::io::handler::IoAsyncHandler;
 use hw_checker::io::IoEvent;
+use hw_checker::report::Report;
+use hw_checker::runner::events::log_events;
+use hw_checker::runner::{default_jobs, RunOptions, Runner, Valgrind};
+use hw_checker::settings::Settings;
 use hw_checker::start_ui;
-use eyre::Result;
 use log::{info, LevelFilter};
 use tokio::time::timeout;
 
-use crate::legacy::run_tests;
+use crate::cli::{Cli, Command, Format};
+use crate::commands::{
+    bless, clean, finish_events, list_tests, record_run, stream_events, write_html_report,
+    write_report,
+};
+use crate::legacy::{progress_to_stderr, run_checkstyle, run_tests};
 
+mod cli;
+mod commands;
 mod legacy;
 
 #[tokio::main]
@@ -17,44 +30,139 @@ async fn main() -> Result<()> {
     env::set_var("RUST_BACKTRACE", "1");
     let (sync_io_tx, sync_io_rx) = tokio::sync::mpsc::channel::<IoEvent>(100);
 
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() > 1 && args[1] == "--legacy" {
-        info!("Running in legacy mode");
-        println!("Running in legacy mode");
+    let cli = Cli::parse();
 
-        let app = App::new(sync_io_tx.clone());
+    // The refs may not exist yet, so the suite is loaded differently
+    if let Command::Bless { solution, yes } = cli.command() {
+        return bless(&cli.options.load_options(), &solution, yes).await;
+    }
 
-        if timeout(Duration::from_millis(595000), run_tests(app))
-            .await
-            .is_err()
-        {
-            println!("\nTests ran for too long, stopping execution");
+    let mut runner = match Runner::load(&cli.options.load_options()) {
+        Ok(runner) => runner,
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(1);
         }
+    };
+
+    let event_writer = match cli.options.events.as_deref() {
+        Some(target) => match stream_events(&runner.events, target).await {
+            Ok(writer) => Some(writer),
+            Err(err) => {
+                eprintln!("{:#}", err);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+
+    match cli.command() {
+        Command::Tui => {}
+        Command::Run { output } => {
+            let format = cli.options.format;
+            if output.is_some() && format == Format::Text {
+                eprintln!("--output needs a --format other than text");
+                exit(2);
+            }
+            // The report goes to stdout, so it must not be mixed with the progress
+            progress_to_stderr(output.is_none() && format != Format::Text);
+
+            info!("Running in legacy mode");
+            if format == Format::Text {
+                println!("Running in legacy mode");
+            }
+
+            let options = RunOptions {
+                valgrind: match cli.options.valgrind() {
+                    Some(true) => Valgrind::On,
+                    Some(false) => Valgrind::Off,
+                    None => Valgrind::from_suite(
+                        runner.suite.valgrind_enabled,
+                        runner.suite.valgrind_below,
+                    ),
+                },
+                jobs: cli.options.jobs.unwrap_or_else(default_jobs),
+            };
+            let run = run_tests(&mut runner, &options);
+            let (built, finished) = match timeout(Duration::from_millis(595000), run).await {
+                Ok(built) => (built, true),
+                Err(_) => {
+                    eprintln!("\nTests ran for too long, stopping execution");
+                    (true, false)
+                }
+            };
+
+            if !built {
+                finish_events(runner, event_writer).await;
+                exit(2);
+            }
+
+            let report = Report::new(&runner);
+            write_report(&report, format, output.as_deref())?;
+            if finished {
+                if let Err(err) = record_run(&runner) {
+                    eprintln!("{:#}", err);
+                }
+                match write_html_report(&report) {
+                    Ok(path) if format == Format::Text => println!("Report written to {path}"),
+                    Ok(_) => {}
+                    Err(err) => eprintln!("{:#}", err),
+                }
+            }
 
-        return Ok(());
+            let passed = finished && report.passed();
+            finish_events(runner, event_writer).await;
+            if !passed {
+                exit(1);
+            }
+            return Ok(());
+        }
+        Command::List => {
+            list_tests(&runner.suite, cli.options.format)?;
+            return Ok(());
+        }
+        Command::CheckStyle => {
+            run_checkstyle(&mut runner).await;
+            finish_events(runner, event_writer).await;
+            return Ok(());
+        }
+        Command::Clean { results } => {
+            clean(&runner.suite, results).await;
+            return Ok(());
+        }
+        // Handled before the suite is loaded
+        Command::Bless { .. } => unreachable!(),
     }
 
+    let events = runner.events.clone();
+    let mut app = App::new(sync_io_tx.clone(), runner);
+    match Settings::load() {
+        Ok(settings) => app.apply_settings(settings),
+        // The defaults are used instead
+        Err(err) => app.runner.suite.warnings.push(format!("{:#}", err)),
+    }
     // We need to share the App between thread
-    let app = Arc::new(tokio::sync::Mutex::new(App::new(sync_io_tx.clone())));
+    let app = Arc::new(tokio::sync::Mutex::new(app));
     let app_ui = Arc::clone(&app);
     let receive = Arc::new(tokio::sync::Mutex::new(sync_io_rx));
 
     // Configure log
-    tui_logger::init_logger(LevelFilter::Info).unwrap();
+    logs::init_logger(LevelFilter::Info).unwrap();
     tui_logger::set_default_level(log::LevelFilter::Info);
+    tokio::spawn(log_events(events.subscribe()));
     // log4rs::init_file("logging_config.yaml", Default::default()).unwrap();
 
     // Handle IO in a specifc thread
 
-    let threads = available_parallelism().unwrap().get();
+    let threads = cli.options.jobs.unwrap_or_else(default_jobs).max(1);
 
     for _ in 0..threads {
         let app_clone = Arc::clone(&app);
         let receive_clone = Arc::clone(&receive);
+        let events = events.clone();
 
         tokio::spawn(async move {
-            let mut handler = IoAsyncHandler::new(app_clone);
+            let mut handler = IoAsyncHandler::new(app_clone, events);
             loop {
                 let mut copy = receive_clone.lock().await;
                 if let Some(io_event) = copy.recv().await {
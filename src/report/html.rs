@@ -0,0 +1,190 @@
+use std::fmt::Write;
+
+use super::{escape, Report, TestReport};
+use crate::suite::format_points;
+use crate::suite::TestStatus;
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 70em; color: #222; }
+h1 .score { float: right; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.2em 0.8em; text-align: left; border-bottom: 1px solid #ddd; }
+td.number { text-align: right; }
+.passed { color: #1a7f37; }
+.failed { color: #cf222e; }
+.warning { color: #9a6700; }
+details { margin: 0.4em 0; border: 1px solid #ddd; border-radius: 4px; padding: 0.4em; }
+summary { cursor: pointer; font-weight: bold; }
+pre { background: #f6f8fa; padding: 0.6em; overflow-x: auto; margin: 0.4em 0; }
+pre.diff span { display: block; white-space: pre; }
+pre.diff .delete { background: #ffebe9; color: #cf222e; }
+pre.diff .insert { background: #dafbe1; color: #1a7f37; }
+pre.diff .hunk { color: #6e7781; }
+";
+
+/// A single HTML page, with the styles inlined so it can be sent as it is
+pub fn render(report: &Report) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+    html.push_str("<meta charset=\"utf-8\">\n<title>hw_checker report</title>\n");
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+
+    let _ = writeln!(
+        html,
+        "<h1>hw_checker report <span class=\"score {}\">{}/{}</span></h1>",
+        if report.score >= report.max_score {
+            "passed"
+        } else {
+            "failed"
+        },
+        format_points(report.score),
+        format_points(report.max_score)
+    );
+
+    write_subtotals(&mut html, report);
+    write_tests(&mut html, report);
+    write_details(&mut html, report);
+    write_checkstyle(&mut html, report);
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn write_subtotals(html: &mut String, report: &Report) {
+    html.push_str("<h2>Score</h2>\n<table>\n<tr><th>Part</th><th>Points</th></tr>\n");
+
+    for row in report.subtotals.iter() {
+        let _ =
+            writeln!(
+            html,
+            "<tr><td style=\"padding-left: {}em\">{}</td><td class=\"number {}\">{}/{}</td></tr>",
+            0.8 + 1.5 * row.depth as f64,
+            escape(&row.name),
+            if row.score >= row.max { "passed" } else { "failed" },
+            format_points(row.score),
+            format_points(row.max)
+        );
+    }
+    let _ = writeln!(
+        html,
+        "<tr><th>Total</th><th class=\"number\">{}/{}</th></tr>\n</table>",
+        format_points(report.score),
+        format_points(report.max_score)
+    );
+}
+
+fn write_tests(html: &mut String, report: &Report) {
+    html.push_str("<h2>Tests</h2>\n<table>\n");
+    html.push_str(
+        "<tr><th>Executable</th><th>Test</th><th>Name</th><th>Status</th><th>Points</th><th>Time</th></tr>\n",
+    );
+
+    for test in report.tests.iter() {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{:02}</td><td>{}</td><td class=\"{}\">{}</td><td class=\"number\">{}/{}</td><td class=\"number\">{:.3}s</td></tr>",
+            escape(&test.exec),
+            test.id,
+            escape(&test.name),
+            status_class(test),
+            escape(&test.status.code()),
+            format_points(test.score),
+            test.max_score,
+            test.time
+        );
+    }
+
+    html.push_str("</table>\n");
+}
+
+/// A collapsible block for every test with a diff or a valgrind summary
+fn write_details(html: &mut String, report: &Report) {
+    let tests: Vec<&TestReport> = report
+        .tests
+        .iter()
+        .filter(|test| !test.diff_hunks.is_empty() || test.valgrind.is_some())
+        .collect();
+    if tests.is_empty() {
+        return;
+    }
+
+    html.push_str("<h2>Details</h2>\n");
+    for test in tests {
+        let _ = writeln!(
+            html,
+            "<details{}>\n<summary><span class=\"{}\">{}</span> {} {:02} {}</summary>",
+            if test.passed { "" } else { " open" },
+            status_class(test),
+            escape(&test.status.to_string()),
+            escape(&test.exec),
+            test.id,
+            escape(&test.name)
+        );
+
+        if let Some(valgrind) = &test.valgrind {
+            let _ = writeln!(
+                html,
+                "<p>Valgrind: {} errors, definitely lost: {}, indirectly lost: {}, possibly lost: {}</p>",
+                valgrind.errors,
+                escape(or_none(&valgrind.definitely_lost)),
+                escape(or_none(&valgrind.indirectly_lost)),
+                escape(or_none(&valgrind.possibly_lost))
+            );
+        }
+
+        if !test.diff_hunks.is_empty() {
+            html.push_str("<pre class=\"diff\">");
+            for (sign, line) in test.diff_hunks.iter() {
+                let (class, sign) = match *sign {
+                    "-" => ("delete", "-"),
+                    "+" => ("insert", "+"),
+                    // The header is written as it is
+                    "@" => ("hunk", ""),
+                    _ => ("equal", " "),
+                };
+                let _ = write!(
+                    html,
+                    "<span class=\"{}\">{}{}</span>",
+                    class,
+                    sign,
+                    escape(line.trim_end_matches('⏎').trim_end_matches('\n'))
+                );
+            }
+            html.push_str("</pre>\n");
+        }
+
+        html.push_str("</details>\n");
+    }
+}
+
+fn write_checkstyle(html: &mut String, report: &Report) {
+    html.push_str("<h2>Checkstyle</h2>\n");
+
+    if report.checkstyle.trim().is_empty() {
+        html.push_str("<p class=\"passed\">No coding style errors found</p>\n");
+    } else {
+        let _ = writeln!(
+            html,
+            "<details open>\n<summary class=\"warning\">{} problems</summary>\n<pre>{}</pre>\n</details>",
+            report.checkstyle_errors,
+            escape(&report.checkstyle)
+        );
+    }
+}
+
+fn status_class(test: &TestReport) -> &'static str {
+    if test.passed {
+        "passed"
+    } else if test.status == TestStatus::MemLeaks && test.score > 0.0 {
+        "warning"
+    } else {
+        "failed"
+    }
+}
+
+fn or_none(text: &str) -> &str {
+    if text.is_empty() {
+        "none"
+    } else {
+        text
+    }
+}
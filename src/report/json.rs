@@ -0,0 +1,6 @@
+use super::Report;
+
+/// The whole report as pretty printed JSON
+pub fn render(report: &Report) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(report)
+}
@@ -0,0 +1,107 @@
+use std::fmt::Write;
+
+use super::{escape, Report, TestReport};
+use crate::suite::format_points;
+
+/// A JUnit XML document, with a test suite for every executable
+pub fn render(report: &Report) -> String {
+    let mut execs: Vec<&str> = Vec::new();
+    for test in report.tests.iter() {
+        if !execs.contains(&test.exec.as_str()) {
+            execs.push(&test.exec);
+        }
+    }
+
+    let failures = report.tests.iter().filter(|test| !test.passed).count();
+    let time: f64 = report.tests.iter().map(|test| test.time).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"hw_checker\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+        report.tests.len(),
+        failures,
+        time
+    );
+
+    for exec in execs {
+        let tests: Vec<&TestReport> = report
+            .tests
+            .iter()
+            .filter(|test| test.exec == exec)
+            .collect();
+
+        let _ = writeln!(
+            xml,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">",
+            escape(exec),
+            tests.len(),
+            tests.iter().filter(|test| !test.passed).count(),
+            tests.iter().map(|test| test.time).sum::<f64>()
+        );
+
+        for test in tests {
+            write_test(&mut xml, test);
+        }
+
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn write_test(xml: &mut String, test: &TestReport) {
+    let _ = writeln!(
+        xml,
+        "    <testcase name=\"{:02} {}\" classname=\"{}\" time=\"{:.3}\">",
+        test.id,
+        escape(&test.name),
+        escape(&test.exec),
+        test.time
+    );
+
+    if !test.passed {
+        let _ = writeln!(
+            xml,
+            "      <failure message=\"{} ({}/{} points)\" type=\"{}\">{}</failure>",
+            escape(&test.status.code()),
+            format_points(test.score),
+            test.max_score,
+            escape(&test.status.to_string()),
+            escape(test.diff.as_deref().unwrap_or_default())
+        );
+    }
+
+    if let Some(valgrind) = &test.valgrind {
+        let _ = writeln!(
+            xml,
+            "      <system-out>valgrind: {} errors, definitely lost: {}, indirectly lost: {}, possibly lost: {}</system-out>",
+            valgrind.errors,
+            escape(&valgrind.definitely_lost),
+            escape(&valgrind.indirectly_lost),
+            escape(&valgrind.possibly_lost)
+        );
+    }
+
+    xml.push_str("    </testcase>\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::sample;
+
+    #[test]
+    fn escapes_the_names_and_the_diff() {
+        let xml = render(&sample("a&b", "<\"quoted\">", "-x < y\n+x > y\u{1b}[0m"));
+
+        assert!(xml.contains("<testsuite name=\"a&amp;b\" tests=\"2\" failures=\"2\""));
+        assert!(xml.contains("name=\"01 &lt;&quot;quoted&quot;&gt;\" classname=\"a&amp;b\""));
+        assert!(xml
+            .contains("message=\"ERROR:Cannot run: &apos;a&apos; &amp; &lt;b&gt; (0/5 points)\""));
+        assert!(xml.contains(">-x &lt; y\n+x &gt; y[0m</failure>"));
+        assert!(xml.contains("possibly lost: it&apos;s 0</system-out>"));
+        assert!(!xml.contains('\u{1b}'));
+    }
+}
@@ -0,0 +1,404 @@
+use std::fs;
+use std::time::Duration;
+
+use serde::Serialize;
+use similar::{Change, ChangeTag, TextDiff};
+
+use crate::runner::Runner;
+use crate::suite::{ScoreRow, TestStatus};
+
+pub mod html;
+pub mod json;
+pub mod junit;
+pub mod tap;
+
+/// Name of the HTML report, written in the test directory
+pub const HTML_REPORT: &str = "report.html";
+
+/// Lines of unified diff kept for every failed test
+const DIFF_EXCERPT_LINES: usize = 20;
+/// Lines of the ref and the output shown around the changes in the HTML report
+const HUNK_CONTEXT: usize = 3;
+/// Lines of hunks kept for every failed test in the HTML report
+const MAX_HUNK_LINES: usize = 200;
+/// Time spent looking for the smallest diff, before settling for a bigger one
+pub(crate) const DIFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Results of a run, in a form every report format can be written from
+#[derive(Serialize, Debug, Clone)]
+pub struct Report {
+    pub tests: Vec<TestReport>,
+    pub subtotals: Vec<ScoreRow>,
+    pub score: f64,
+    pub max_score: f64,
+    pub checkstyle_errors: usize,
+    /// Findings of the coding style checker, as printed by it
+    pub checkstyle: String,
+    pub valgrind_enabled: bool,
+    /// Where the refs and the valgrind logs are read from
+    #[serde(skip)]
+    pub test_path: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct TestReport {
+    pub exec: String,
+    pub id: usize,
+    pub name: String,
+    pub status: TestStatus,
+    pub passed: bool,
+    pub score: f64,
+    pub max_score: usize,
+    /// Seconds spent by the last run, with or without valgrind
+    pub time: f64,
+    pub valgrind: Option<ValgrindSummary>,
+    /// The start of the unified diff between the ref and the output
+    pub diff: Option<String>,
+    /// The changed lines of the ref and the output with some context, from
+    /// [`diff_hunks`]
+    #[serde(skip)]
+    pub diff_hunks: Vec<(&'static str, String)>,
+    /// Tests that never ran have no output or valgrind log to read
+    #[serde(skip)]
+    pub ran: bool,
+    /// The output of a test that ran and did not match the ref, until it is diffed
+    #[serde(skip)]
+    pub output: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValgrindSummary {
+    pub errors: usize,
+    pub definitely_lost: String,
+    pub indirectly_lost: String,
+    pub possibly_lost: String,
+}
+
+impl Report {
+    /// Collects the results of the runner, reading the refs and the valgrind logs
+    /// from the test directory
+    pub fn new(runner: &Runner) -> Self {
+        let mut report = Self::snapshot(runner);
+        report.read_files();
+
+        report
+    }
+
+    /// Collects the results of the runner without reading any file, for the runner
+    /// to be let go before [`Report::read_files`]
+    pub fn snapshot(runner: &Runner) -> Self {
+        let breakdown = runner.score_breakdown();
+        let suite = &runner.suite;
+
+        let tests = suite
+            .exec_name
+            .iter()
+            .zip(suite.tests.iter())
+            .flat_map(|(exec, tests)| {
+                tests.iter().map(move |test| {
+                    let score = suite.scoring.test_points(test);
+                    let passed = score >= test.test_score as f64 && test.passed_output;
+                    // Tests that never ran have no output to compare
+                    let ran = test.status != TestStatus::Pending && !test.status.is_active();
+
+                    TestReport {
+                        exec: exec.clone(),
+                        id: test.id,
+                        name: test.name.clone(),
+                        status: test.status.clone(),
+                        passed,
+                        score,
+                        max_score: test.test_score,
+                        time: test.time(suite.valgrind_enabled),
+                        valgrind: None,
+                        diff: None,
+                        diff_hunks: Vec::new(),
+                        ran,
+                        output: (ran && !test.passed_output).then(|| test.log.clone()),
+                    }
+                })
+            })
+            .collect();
+
+        Self {
+            tests,
+            subtotals: breakdown.rows().cloned().collect(),
+            score: breakdown.total,
+            max_score: breakdown.max,
+            checkstyle_errors: runner.checkstyle.problems(),
+            checkstyle: runner.checkstyle.output.clone(),
+            valgrind_enabled: suite.valgrind_enabled,
+            test_path: suite.test_path.clone(),
+        }
+    }
+
+    /// Diffs the outputs of the failed tests with their refs and reads the valgrind
+    /// logs of the tests that ran. It blocks on the files and the diffs.
+    pub fn read_files(&mut self) {
+        for test in self.tests.iter_mut().filter(|test| test.ran) {
+            if self.valgrind_enabled {
+                test.valgrind = fs::read_to_string(format!(
+                    "{}output/{:02}-{}.valgrind",
+                    self.test_path, test.id, test.exec
+                ))
+                .ok()
+                .map(|log| ValgrindSummary::parse(&log));
+            }
+
+            let Some(output) = test.output.take() else {
+                continue;
+            };
+            let reference = fs::read_to_string(format!(
+                "{}ref/{:02}-{}.ref",
+                self.test_path, test.id, test.exec
+            ));
+            if let Ok(reference) = reference {
+                test.diff = Some(diff_excerpt(&reference, &output)).filter(|diff| !diff.is_empty());
+                test.diff_hunks = diff_hunks(&reference, &output);
+            }
+        }
+    }
+
+    /// True when every test got all of its points
+    pub fn passed(&self) -> bool {
+        self.tests.iter().all(|test| test.passed)
+    }
+}
+
+impl ValgrindSummary {
+    /// Reads the summary lines of a valgrind log
+    pub fn parse(log: &str) -> Self {
+        let mut summary = Self::default();
+
+        for line in log.lines() {
+            // Lines start with the pid, as in "==1234== definitely lost: ..."
+            let line = line
+                .trim_start_matches(|c: char| c == '=' || c.is_ascii_digit())
+                .trim();
+
+            if let Some(rest) = line.strip_prefix("ERROR SUMMARY:") {
+                summary.errors = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(|errors| errors.replace(',', "").parse().ok())
+                    .unwrap_or(0);
+            } else if let Some(rest) = line.strip_prefix("definitely lost:") {
+                summary.definitely_lost = rest.trim().to_string();
+            } else if let Some(rest) = line.strip_prefix("indirectly lost:") {
+                summary.indirectly_lost = rest.trim().to_string();
+            } else if let Some(rest) = line.strip_prefix("possibly lost:") {
+                summary.possibly_lost = rest.trim().to_string();
+            }
+        }
+
+        summary
+    }
+}
+
+/// The first lines of the unified diff between the ref and the output
+pub fn diff_excerpt(reference: &str, output: &str) -> String {
+    let diff = line_diff(reference, output)
+        .unified_diff()
+        .context_radius(2)
+        .header("ref", "output")
+        .to_string();
+
+    let mut lines: Vec<&str> = diff.lines().take(DIFF_EXCERPT_LINES + 1).collect();
+    if lines.len() > DIFF_EXCERPT_LINES {
+        lines.truncate(DIFF_EXCERPT_LINES);
+        lines.push("...");
+    }
+
+    lines.join("\n")
+}
+
+/// Every line of the ref and the output, marked with `-` when it is only in the
+/// ref, `+` when it is only in the output and a space when they match
+pub fn diff_lines(reference: &str, output: &str) -> Vec<(&'static str, String)> {
+    line_diff(reference, output)
+        .iter_all_changes()
+        .map(signed_line)
+        .collect()
+}
+
+/// The lines of [`diff_lines`] around the changes, each hunk starting with its
+/// `@@` header marked with `@`. Long diffs are cut after [`MAX_HUNK_LINES`] lines.
+pub fn diff_hunks(reference: &str, output: &str) -> Vec<(&'static str, String)> {
+    let diff = line_diff(reference, output);
+    let mut unified = diff.unified_diff();
+    unified.context_radius(HUNK_CONTEXT);
+
+    let mut lines = Vec::new();
+    for hunk in unified.iter_hunks() {
+        lines.push(("@", hunk.header().to_string()));
+        lines.extend(hunk.iter_changes().map(signed_line));
+    }
+
+    if lines.len() > MAX_HUNK_LINES {
+        let cut = lines.len() - MAX_HUNK_LINES;
+        lines.truncate(MAX_HUNK_LINES);
+        lines.push(("@", format!("... {} more lines", cut)));
+    }
+
+    lines
+}
+
+/// Diffs the lines of the ref and the output. Long outputs that differ everywhere take
+/// too long to diff exactly, so their diff is not the smallest after [`DIFF_TIMEOUT`].
+fn line_diff<'a>(reference: &'a str, output: &'a str) -> TextDiff<'a, 'a, 'a, str> {
+    TextDiff::configure()
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(reference, output)
+}
+
+fn signed_line(change: Change<&str>) -> (&'static str, String) {
+    let sign = match change.tag() {
+        ChangeTag::Delete => "-",
+        ChangeTag::Insert => "+",
+        ChangeTag::Equal => " ",
+    };
+
+    match change.missing_newline() {
+        true => (sign, format!("{}", change)),
+        false => (sign, format!("{}⏎", change)),
+    }
+}
+
+/// Escapes the text of an XML or HTML attribute or element
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Not allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\t' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    /// A report of a failed test with these names and diff, and a leaking one
+    pub(super) fn sample(exec: &str, name: &str, diff: &str) -> Report {
+        let failed = TestReport {
+            exec: exec.to_string(),
+            id: 1,
+            name: name.to_string(),
+            status: TestStatus::Error {
+                reason: String::from("Cannot run: 'a' & <b>"),
+            },
+            passed: false,
+            score: 0.0,
+            max_score: 5,
+            time: 0.25,
+            valgrind: None,
+            diff: Some(diff.to_string()),
+            diff_hunks: Vec::new(),
+            ran: true,
+            output: None,
+        };
+        let leaking = TestReport {
+            id: 2,
+            status: TestStatus::MemLeaks,
+            valgrind: Some(ValgrindSummary {
+                errors: 1,
+                definitely_lost: String::from("8 bytes in 1 block"),
+                indirectly_lost: String::from("0 bytes in 0 blocks"),
+                possibly_lost: String::from("it's 0"),
+            }),
+            diff: None,
+            ..failed.clone()
+        };
+
+        Report {
+            tests: vec![failed, leaking],
+            subtotals: Vec::new(),
+            score: 0.0,
+            max_score: 10.0,
+            checkstyle_errors: 0,
+            checkstyle: String::new(),
+            valgrind_enabled: true,
+            test_path: String::new(),
+        }
+    }
+
+    #[test]
+    fn escapes_markup_and_drops_invalid_characters() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape("bell\u{7}\tend\n"), "bell\tend\n");
+    }
+
+    #[test]
+    fn excerpts_are_cut() {
+        let reference: String = (0..100).map(|line| format!("{}\n", line)).collect();
+        let excerpt = diff_excerpt(&reference, "");
+
+        assert!(excerpt.starts_with("--- ref\n+++ output\n"));
+        assert_eq!(excerpt.lines().count(), DIFF_EXCERPT_LINES + 1);
+        assert!(excerpt.ends_with("\n..."));
+        assert_eq!(diff_excerpt("same\n", "same\n"), "");
+    }
+
+    #[test]
+    fn hunks_keep_the_changes_and_their_context() {
+        let reference: String = (0..20).map(|line| format!("{}\n", line)).collect();
+        let output = reference.replace("10\n", "ten\n");
+
+        assert_eq!(
+            diff_hunks(&reference, &output),
+            [
+                ("@", String::from("@@ -8,7 +8,7 @@")),
+                (" ", String::from("7\n⏎")),
+                (" ", String::from("8\n⏎")),
+                (" ", String::from("9\n⏎")),
+                ("-", String::from("10\n⏎")),
+                ("+", String::from("ten\n⏎")),
+                (" ", String::from("11\n⏎")),
+                (" ", String::from("12\n⏎")),
+                (" ", String::from("13\n⏎")),
+            ]
+        );
+
+        let long = diff_hunks(&reference.repeat(100), "");
+        assert_eq!(long.len(), MAX_HUNK_LINES + 1);
+        assert_eq!(long[MAX_HUNK_LINES].1, "... 1801 more lines");
+    }
+
+    #[test]
+    fn large_mismatched_outputs_are_diffed_in_time() {
+        // Lines in a shuffled order, which takes minutes to diff exactly
+        let reference: String = (0..50_000).map(|line| format!("{}\n", line)).collect();
+        let output: String = (0..50_000)
+            .map(|line| format!("{}\n", line * 7 % 50_000))
+            .collect();
+
+        let start = Instant::now();
+        let diff = diff_lines(&reference, &output);
+        assert!(start.elapsed() < DIFF_TIMEOUT * 10);
+
+        // Both sides can still be read from the diff
+        let side = |sign| {
+            diff.iter()
+                .filter(|(line_sign, _)| *line_sign == " " || *line_sign == sign)
+                .map(|(_, line)| line.trim_end_matches('⏎'))
+                .collect::<String>()
+        };
+        assert_eq!(side("-"), reference);
+        assert_eq!(side("+"), output);
+    }
+}
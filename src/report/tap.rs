@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+use super::Report;
+use crate::suite::format_points;
+
+/// A TAP version 13 stream, with a YAML block for every failed test
+pub fn render(report: &Report) -> String {
+    let mut tap = String::from("TAP version 13\n");
+    let _ = writeln!(tap, "1..{}", report.tests.len());
+
+    for (number, test) in report.tests.iter().enumerate() {
+        let _ = writeln!(
+            tap,
+            "{} {} - {} {:02} {}",
+            if test.passed { "ok" } else { "not ok" },
+            number + 1,
+            test.exec,
+            test.id,
+            // '#' starts a directive, and the description ends with the line
+            test.name.replace('#', "\\#").replace(['\n', '\r'], " ")
+        );
+
+        if test.passed && test.valgrind.is_none() {
+            continue;
+        }
+
+        tap.push_str("  ---\n");
+        let _ = writeln!(tap, "  status: {}", quote(&test.status.code()));
+        let _ = writeln!(
+            tap,
+            "  score: {}/{}",
+            format_points(test.score),
+            test.max_score
+        );
+        let _ = writeln!(tap, "  time: {:.3}", test.time);
+        if let Some(valgrind) = &test.valgrind {
+            tap.push_str("  valgrind:\n");
+            let _ = writeln!(tap, "    errors: {}", valgrind.errors);
+            let _ = writeln!(
+                tap,
+                "    definitely_lost: {}",
+                quote(&valgrind.definitely_lost)
+            );
+            let _ = writeln!(
+                tap,
+                "    indirectly_lost: {}",
+                quote(&valgrind.indirectly_lost)
+            );
+            let _ = writeln!(tap, "    possibly_lost: {}", quote(&valgrind.possibly_lost));
+        }
+        if let Some(diff) = &test.diff {
+            tap.push_str("  diff: |\n");
+            for line in diff.lines() {
+                let _ = writeln!(tap, "    {}", line);
+            }
+        }
+        tap.push_str("  ...\n");
+    }
+
+    let _ = writeln!(
+        tap,
+        "# Score: {}/{}",
+        format_points(report.score),
+        format_points(report.max_score)
+    );
+    tap
+}
+
+/// A single-quoted YAML scalar, on one line
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''").replace(['\n', '\r'], " "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::tests::sample;
+
+    #[test]
+    fn keeps_the_names_and_the_yaml_on_their_lines() {
+        let tap = render(&sample("exec", "a # b\nok 3 - fake", "-x\n+y"));
+
+        assert!(tap.starts_with("TAP version 13\n1..2\n"));
+        assert!(tap.contains("not ok 1 - exec 01 a \\# b ok 3 - fake\n"));
+        assert!(!tap.lines().any(|line| line.starts_with("ok ")));
+        assert!(tap.contains("  status: 'ERROR:Cannot run: ''a'' & <b>'\n"));
+        assert!(tap.contains("    possibly_lost: 'it''s 0'\n"));
+        assert!(tap.contains("  diff: |\n    -x\n    +y\n  ...\n"));
+        assert!(tap.ends_with("# Score: 0/10\n"));
+    }
+}
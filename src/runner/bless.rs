@@ -0,0 +1,183 @@
+//! Writes the refs from the outputs of a reference solution, for the course staff
+
+use std::io::{self, ErrorKind};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::fs;
+use tokio::process::Command;
+use tokio::time::{timeout, Instant};
+
+use super::Job;
+use crate::suite::Test;
+
+/// The reference solution gets this many times the timeout of a test, so the timeout
+/// can be suggested even when it is too tight
+const TIMEOUT_MARGIN: u64 = 10;
+/// Homeworks are usually slower than the reference solution
+const SUGGESTED_SLOWDOWN: f64 = 5.0;
+/// Milliseconds, suggested timeouts are rounded up to it
+const SUGGESTED_STEP: u64 = 500;
+const MIN_SUGGESTED_TIMEOUT: u64 = 1000;
+
+/// Name of the timings of the reference solution, written in the test directory
+pub const TIMINGS_FILE: &str = "timings.json";
+
+/// The output of the reference solution on the input of a test, to replace its ref
+#[derive(Debug, Clone)]
+pub struct Blessed {
+    pub exec: String,
+    pub id: usize,
+    /// Where the ref is written
+    pub path: String,
+    /// The ref on disk, `None` for a new test
+    pub old: Option<String>,
+    pub new: String,
+    /// Seconds
+    pub time: f64,
+    /// Milliseconds, from the manifest
+    pub timeout: u64,
+}
+
+/// The time the reference solution took on a test, kept after `bless` to set the
+/// timeouts from
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Timing {
+    pub exec: String,
+    pub id: usize,
+    /// Milliseconds
+    pub time: f64,
+    /// Milliseconds, from the manifest
+    pub timeout: u64,
+    /// Milliseconds, see [`Blessed::suggested_timeout`]
+    pub suggested_timeout: u64,
+}
+
+impl Blessed {
+    /// Runs the executable of the reference solution in `solution` on the input of the
+    /// test, one test at a time so the timings are not skewed
+    pub async fn run(
+        solution: &Path,
+        test_path: &str,
+        exec: &str,
+        test: &Test,
+    ) -> io::Result<Self> {
+        let job = Job::new(test_path, exec, test, false);
+        let program = solution.join(exec);
+        let input = std::fs::File::open(job.path("input", "in"))?;
+        let old = match fs::read_to_string(job.path("ref", "ref")).await {
+            Ok(old) => Some(old),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+
+        let start = Instant::now();
+        let child = Command::new(&program)
+            .stdin(input)
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!("Cannot run {}: {}", program.display(), err),
+                )
+            })?;
+
+        let limit = Duration::from_millis(test.timeout * TIMEOUT_MARGIN);
+        let output = match timeout(limit, child.wait_with_output()).await {
+            Ok(output) => output?,
+            Err(_) => {
+                return Err(io::Error::new(
+                    ErrorKind::TimedOut,
+                    format!("Still running after {} ms", limit.as_millis()),
+                ))
+            }
+        };
+        let time = start.elapsed().as_secs_f64();
+
+        if output.status.code().is_none() {
+            return Err(io::Error::other(format!("Crashed, {}", output.status)));
+        }
+
+        // The tests read the output line by line, so the ref is written the same way
+        let new = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| format!("{}\n", line))
+            .collect();
+
+        Ok(Self {
+            exec: exec.to_string(),
+            id: test.id,
+            path: job.path("ref", "ref"),
+            old,
+            new,
+            time,
+            timeout: test.timeout,
+        })
+    }
+
+    /// The ref is new or differs from the one on disk
+    pub fn changed(&self) -> bool {
+        self.old.as_deref() != Some(self.new.as_str())
+    }
+
+    /// Milliseconds, a few times the time of the reference solution
+    pub fn suggested_timeout(&self) -> u64 {
+        let timeout = (self.time * 1000.0 * SUGGESTED_SLOWDOWN).ceil() as u64;
+
+        (timeout.div_ceil(SUGGESTED_STEP) * SUGGESTED_STEP).max(MIN_SUGGESTED_TIMEOUT)
+    }
+
+    pub fn timing(&self) -> Timing {
+        Timing {
+            exec: self.exec.clone(),
+            id: self.id,
+            time: self.time * 1000.0,
+            timeout: self.timeout,
+            suggested_timeout: self.suggested_timeout(),
+        }
+    }
+
+    pub async fn write(&self) -> io::Result<()> {
+        if let Some(directory) = Path::new(&self.path).parent() {
+            fs::create_dir_all(directory).await?;
+        }
+
+        fs::write(&self.path, &self.new).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blessed(time: f64) -> Blessed {
+        Blessed {
+            exec: String::from("feed"),
+            id: 3,
+            path: String::from("tests/ref/03-feed.ref"),
+            old: None,
+            new: String::new(),
+            time,
+            timeout: 1000,
+        }
+    }
+
+    #[test]
+    fn timings_keep_the_suggested_timeout() {
+        assert_eq!(
+            blessed(0.3).timing(),
+            Timing {
+                exec: String::from("feed"),
+                id: 3,
+                time: 300.0,
+                timeout: 1000,
+                suggested_timeout: 1500,
+            }
+        );
+        assert_eq!(blessed(0.001).suggested_timeout(), MIN_SUGGESTED_TIMEOUT);
+    }
+}
@@ -0,0 +1,213 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{debug, info, warn};
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::suite::TestStatus;
+
+/// Events kept for a subscriber that falls behind, older ones are dropped
+const CAPACITY: usize = 4096;
+
+/// Something that happened while building the homework or running its tests
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+    BuildStarted,
+    BuildFinished {
+        success: bool,
+        /// What make printed, or why it failed
+        output: String,
+    },
+    TestQueued {
+        exec: String,
+        id: usize,
+    },
+    TestStarted {
+        exec: String,
+        id: usize,
+        valgrind: bool,
+    },
+    /// Lines printed by the test since the last chunk
+    TestOutput {
+        exec: String,
+        id: usize,
+        chunk: String,
+    },
+    TestFinished {
+        exec: String,
+        id: usize,
+        status: TestStatus,
+        /// Seconds
+        time: f64,
+        passed_output: bool,
+    },
+    CheckstyleFinished {
+        /// Number of checks, warnings and errors found
+        problems: usize,
+    },
+    /// Never published, received in place of the events a subscriber lost by falling
+    /// behind
+    Lagged {
+        skipped: u64,
+    },
+}
+
+/// Publishes the progress to every subscriber, cloned wherever events are sent from
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ProgressEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+
+    /// Events are only lost when nobody subscribed
+    pub fn publish(&self, event: ProgressEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Receives the events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Waits for the next event, or a `Lagged` event when some were lost by falling behind.
+/// Returns `None` once every bus is dropped.
+pub async fn next_event(events: &mut broadcast::Receiver<ProgressEvent>) -> Option<ProgressEvent> {
+    match events.recv().await {
+        Ok(event) => Some(event),
+        Err(RecvError::Lagged(skipped)) => Some(ProgressEvent::Lagged { skipped }),
+        Err(RecvError::Closed) => None,
+    }
+}
+
+/// Writes the events to the log
+pub async fn log_events(mut events: broadcast::Receiver<ProgressEvent>) {
+    while let Some(event) = next_event(&mut events).await {
+        match event {
+            ProgressEvent::BuildStarted => info!("Running makefile"),
+            ProgressEvent::BuildFinished {
+                success: true,
+                output,
+            } => info!("\n{}", output),
+            ProgressEvent::BuildFinished { output, .. } => warn!("Build failed\n{}", output),
+            ProgressEvent::TestQueued { .. } | ProgressEvent::TestOutput { .. } => {}
+            ProgressEvent::TestStarted { exec, id, valgrind } => {
+                info!("Running {} with test number {}", exec, id);
+                debug!("Valgrind enabled: {}", valgrind);
+            }
+            ProgressEvent::TestFinished {
+                exec,
+                id,
+                status,
+                time,
+                ..
+            } => match status {
+                TestStatus::Error { reason } => {
+                    warn!("Cannot run {} test {}: {}", exec, id, reason)
+                }
+                status => debug!("{} test {} ended with {} in {:.5}s", exec, id, status, time),
+            },
+            ProgressEvent::CheckstyleFinished { problems } => {
+                info!("Checkstyle found {} problems", problems)
+            }
+            ProgressEvent::Lagged { skipped } => {
+                warn!("{} progress events were lost", skipped)
+            }
+        }
+    }
+}
+
+/// A line of the JSON lines stream
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    /// Seconds since the unix epoch
+    timestamp: f64,
+    #[serde(flatten)]
+    event: &'a ProgressEvent,
+}
+
+/// Writes every event as a line of JSON, until every bus is dropped. The lines are
+/// flushed once the events received so far are written.
+pub async fn write_json_lines<W: AsyncWrite + Unpin>(
+    mut events: broadcast::Receiver<ProgressEvent>,
+    writer: W,
+) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    while let Some(event) = next_event(&mut events).await {
+        let line = JsonLine {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs_f64())
+                .unwrap_or(0.0),
+            event: &event,
+        };
+
+        let mut json = serde_json::to_vec(&line)?;
+        json.push(b'\n');
+        writer.write_all(&json).await?;
+        if events.is_empty() {
+            writer.flush().await?;
+        }
+    }
+
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(id: usize) -> ProgressEvent {
+        ProgressEvent::TestQueued {
+            exec: String::from("feed"),
+            id,
+        }
+    }
+
+    #[tokio::test]
+    async fn lagging_subscribers_are_told_how_many_events_they_lost() {
+        let bus = EventBus::new();
+        let mut events = bus.subscribe();
+        for id in 0..CAPACITY + 3 {
+            bus.publish(queued(id));
+        }
+
+        assert_eq!(
+            next_event(&mut events).await,
+            Some(ProgressEvent::Lagged { skipped: 3 })
+        );
+        assert_eq!(next_event(&mut events).await, Some(queued(3)));
+    }
+
+    #[tokio::test]
+    async fn lost_events_are_written_as_a_json_line() {
+        let bus = EventBus::new();
+        let events = bus.subscribe();
+        for id in 0..CAPACITY + 1 {
+            bus.publish(queued(id));
+        }
+        drop(bus);
+
+        let mut written = Vec::new();
+        write_json_lines(events, &mut written).await.unwrap();
+        let written = String::from_utf8(written).unwrap();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), CAPACITY + 1);
+        assert!(lines[0].contains(r#""event":"lagged","skipped":1"#));
+        assert!(lines[CAPACITY].contains(&format!(r#""id":{}"#, CAPACITY)));
+    }
+}
@@ -0,0 +1,251 @@
+use std::io;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::fs::{self, File};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::time::{timeout, Instant};
+
+use super::{EventBus, ProgressEvent};
+use crate::suite::{Test, TestStatus};
+
+/// Exit code given to valgrind, to tell leaks apart from the exit code of the homework
+const VALGRIND_EXIT_CODE: i32 = 69;
+
+/// Bytes of output sent in a single event, when the test prints faster than it is read
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Everything needed to run a single test, detached from the suite so it can
+/// be moved to another task
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub test_path: String,
+    pub exec: String,
+    pub id: usize,
+    /// Milliseconds
+    pub timeout: u64,
+    pub score: usize,
+    pub valgrind: bool,
+}
+
+/// What a job found, to be stored in its test
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub status: TestStatus,
+    /// The output of the program, with the reason of the failure appended
+    pub log: String,
+    /// The output matched the ref, even if valgrind found leaks
+    pub passed_output: bool,
+    /// Seconds
+    pub time: f64,
+    pub valgrind: bool,
+}
+
+impl Job {
+    pub fn new(test_path: &str, exec: &str, test: &Test, valgrind: bool) -> Self {
+        Self {
+            test_path: test_path.to_string(),
+            exec: exec.to_string(),
+            id: test.id,
+            timeout: test.timeout,
+            score: test.test_score,
+            valgrind,
+        }
+    }
+
+    /// `NN-exec.<extension>` in the `directory` of the test path
+    pub fn path(&self, directory: &str, extension: &str) -> String {
+        format!(
+            "{}{}/{:02}-{}.{}",
+            self.test_path, directory, self.id, self.exec, extension
+        )
+    }
+
+    /// Runs the executable on the input, writes its output and compares it with the ref
+    pub async fn run(&self, events: &EventBus) -> Outcome {
+        events.publish(ProgressEvent::TestStarted {
+            exec: self.exec.clone(),
+            id: self.id,
+            valgrind: self.valgrind,
+        });
+        let start = Instant::now();
+
+        let outcome = match self.execute(start, events).await {
+            Ok(outcome) => outcome,
+            Err(err) => self.outcome(
+                TestStatus::Error {
+                    reason: err.to_string(),
+                },
+                err.to_string(),
+                start,
+            ),
+        };
+
+        events.publish(ProgressEvent::TestFinished {
+            exec: self.exec.clone(),
+            id: self.id,
+            status: outcome.status.clone(),
+            time: outcome.time,
+            passed_output: outcome.passed_output,
+        });
+        outcome
+    }
+
+    async fn execute(&self, start: Instant, events: &EventBus) -> io::Result<Outcome> {
+        let reference = match fs::read(self.path("ref", "ref")).await {
+            Ok(reference) => reference,
+            Err(err) => {
+                return Ok(self.outcome(
+                    TestStatus::Error {
+                        reason: String::from("missing ref"),
+                    },
+                    format!("Cannot read {}: {}", self.path("ref", "ref"), err),
+                    start,
+                ))
+            }
+        };
+        let input = std::fs::File::open(self.path("input", "in"))?;
+        let mut out_file = File::create(self.path("output", "out")).await?;
+
+        let mut command = if self.valgrind {
+            let mut command = Command::new("valgrind");
+            command
+                .arg(format!("--log-file={}", self.path("output", "valgrind")))
+                .arg("--leak-check=full")
+                .arg("--track-origins=yes")
+                .arg("--show-leak-kinds=all")
+                .arg(format!("--error-exitcode={}", VALGRIND_EXIT_CODE))
+                .arg(format!("./{}", self.exec));
+            command
+        } else {
+            // A log left by an earlier run would end up in the report
+            let _ = fs::remove_file(self.path("output", "valgrind")).await;
+            Command::new(format!("./{}", self.exec))
+        };
+
+        let program = if self.valgrind {
+            "valgrind"
+        } else {
+            &self.exec
+        };
+        let mut child = command
+            .stdin(input)
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| {
+                io::Error::new(err.kind(), format!("Cannot run {}: {}", program, err))
+            })?;
+
+        let mut log = String::new();
+        if let Some(stdout) = child.stdout.take() {
+            let mut lines = BufReader::new(stdout).lines();
+            // Where the output not sent yet starts in the log
+            let mut sent = 0;
+
+            loop {
+                let time_left =
+                    (self.timeout as u128).saturating_sub(start.elapsed().as_millis()) as u64;
+                let timed_out =
+                    match timeout(Duration::from_millis(time_left), lines.next_line()).await {
+                        Ok(Ok(Some(line))) => {
+                            log.push_str(&line);
+                            log.push('\n');
+
+                            // Lines already read are sent together
+                            let buffered = lines.get_ref().buffer();
+                            if log.len() - sent >= CHUNK_SIZE || !buffered.contains(&b'\n') {
+                                self.send_output(&log[sent..], events);
+                                sent = log.len();
+                            }
+                            start.elapsed().as_millis() > self.timeout as u128
+                        }
+                        Ok(_) => break,
+                        Err(_) => true,
+                    };
+
+                if timed_out {
+                    self.send_output(&log[sent..], events);
+                    child.kill().await?;
+                    out_file.write_all(log.as_bytes()).await?;
+
+                    return Ok(self.outcome(TestStatus::Timeout, log, start));
+                }
+            }
+            self.send_output(&log[sent..], events);
+        }
+
+        let status = child.wait().await?;
+        out_file.write_all(log.as_bytes()).await?;
+        let passed_output = log.as_bytes() == reference.as_slice();
+
+        if status.code().is_none() {
+            log.push_str(&format!("Crashed, {}\n", status));
+
+            return Ok(self.outcome(
+                TestStatus::Crashed {
+                    signal: status.signal(),
+                },
+                log,
+                start,
+            ));
+        }
+
+        if self.valgrind && status.code() == Some(VALGRIND_EXIT_CODE) {
+            log.push_str("MEMLEAKS: Check the .valgrind file in the output folder\n");
+
+            let mut outcome = self.outcome(TestStatus::MemLeaks, log, start);
+            // Leaky programs can still get part of the points
+            outcome.passed_output = passed_output;
+            return Ok(outcome);
+        }
+
+        let status = if passed_output {
+            TestStatus::Passed {
+                points: self.score as f64,
+            }
+        } else {
+            TestStatus::Failed
+        };
+        let mut outcome = self.outcome(status, log, start);
+        outcome.passed_output = passed_output;
+
+        Ok(outcome)
+    }
+
+    fn send_output(&self, chunk: &str, events: &EventBus) {
+        if !chunk.is_empty() {
+            events.publish(ProgressEvent::TestOutput {
+                exec: self.exec.clone(),
+                id: self.id,
+                chunk: chunk.to_string(),
+            });
+        }
+    }
+
+    fn outcome(&self, status: TestStatus, log: String, start: Instant) -> Outcome {
+        Outcome {
+            status,
+            log,
+            passed_output: false,
+            time: start.elapsed().as_secs_f64(),
+            valgrind: self.valgrind,
+        }
+    }
+}
+
+impl Outcome {
+    /// Stores the outcome in the test it was run for
+    pub fn apply(self, test: &mut Test) {
+        test.status = self.status;
+        test.log = self.log;
+        test.passed_output = self.passed_output;
+
+        if self.valgrind {
+            test.time_valgrind = self.time;
+        } else {
+            test.time_normal = self.time;
+        }
+    }
+}
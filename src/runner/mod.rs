@@ -0,0 +1,241 @@
+//! Builds the homework and runs its tests, without any user interface.
+//!
+//! The interactive checker and the `run` subcommand are both built on it, and
+//! other tools can use [`Runner`] to run a suite and read its results.
+
+use std::io;
+use std::sync::Arc;
+use std::thread::available_parallelism;
+
+use tokio::sync::{broadcast, Semaphore};
+use tokio::task::JoinSet;
+
+use crate::suite::{
+    validate, InvalidSuite, LoadOptions, Results, ScoreBreakdown, Suite, Test, TestStatus,
+    UnstoredRun,
+};
+
+pub mod bless;
+pub mod events;
+pub mod job;
+pub mod tools;
+
+pub use self::bless::{Blessed, Timing, TIMINGS_FILE};
+pub use self::events::{EventBus, ProgressEvent};
+pub use self::job::{Job, Outcome};
+pub use self::tools::{build, checkstyle, BuildError, Checkstyle};
+
+/// Which tests run under valgrind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Valgrind {
+    Off,
+    On,
+    /// Only the tests with a lower id, the later ones are usually too slow for it
+    Below(usize),
+}
+
+impl Valgrind {
+    /// Valgrind as set for the suite, when the command line does not choose
+    pub fn from_suite(enabled: bool, below: Option<usize>) -> Self {
+        match (enabled, below) {
+            (false, _) => Self::Off,
+            (true, Some(limit)) => Self::Below(limit),
+            (true, None) => Self::On,
+        }
+    }
+
+    pub fn enabled_for(&self, id: usize) -> bool {
+        match *self {
+            Self::Off => false,
+            Self::On => true,
+            Self::Below(limit) => id < limit,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    pub valgrind: Valgrind,
+    /// Number of tests run at the same time
+    pub jobs: usize,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            valgrind: Valgrind::On,
+            jobs: default_jobs(),
+        }
+    }
+}
+
+/// One job per core
+pub fn default_jobs() -> usize {
+    available_parallelism()
+        .map(|cores| cores.get())
+        .unwrap_or(1)
+}
+
+/// Progress of [`run_tests`]
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'a> {
+    /// The tests of the executable at this index are about to run
+    Executable(usize),
+    /// A test ended, with its outcome stored in `test`
+    Finished {
+        exec: usize,
+        index: usize,
+        test: &'a Test,
+    },
+}
+
+/// Runs every test, one executable after the other and `options.jobs` tests at
+/// a time, storing the outcomes in `tests`
+pub async fn run_tests(
+    tests: &mut [Vec<Test>],
+    exec_name: &[String],
+    test_path: &str,
+    options: &RunOptions,
+    events: &EventBus,
+    mut on_event: impl FnMut(Event<'_>),
+) {
+    // Tests that are not reached, when the run is stopped, keep no old result
+    for test in tests.iter_mut().flatten() {
+        test.status = TestStatus::Pending;
+        test.passed_output = false;
+        test.log.clear();
+        test.time_normal = 0.0;
+        test.time_valgrind = 0.0;
+    }
+
+    let semaphore = Arc::new(Semaphore::new(options.jobs.max(1)));
+    for (exec, list) in tests.iter_mut().enumerate() {
+        on_event(Event::Executable(exec));
+
+        let mut running = JoinSet::new();
+        for (index, test) in list.iter().enumerate() {
+            let job = Job::new(
+                test_path,
+                &exec_name[exec],
+                test,
+                options.valgrind.enabled_for(test.id),
+            );
+            let semaphore = Arc::clone(&semaphore);
+            let events = events.clone();
+
+            events.publish(ProgressEvent::TestQueued {
+                exec: job.exec.clone(),
+                id: job.id,
+            });
+            running.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                (index, job.run(&events).await)
+            });
+        }
+
+        while let Some(finished) = running.join_next().await {
+            // A job only panics if the runtime is shutting down
+            let Ok((index, outcome)) = finished else {
+                continue;
+            };
+
+            let test = &mut list[index];
+            outcome.apply(test);
+            on_event(Event::Finished { exec, index, test });
+        }
+    }
+}
+
+/// A loaded test suite, ready to be run
+#[derive(Debug, Clone)]
+pub struct Runner {
+    pub suite: Suite,
+    pub checkstyle: Checkstyle,
+    /// The progress of the build, the tests and the checkstyle
+    pub events: EventBus,
+}
+
+impl Runner {
+    /// Loads and validates the test suite
+    ///
+    /// # Errors
+    ///
+    /// If the suite cannot be read or it cannot be run, with all the problems found
+    pub fn load(options: &LoadOptions) -> Result<Self, InvalidSuite> {
+        let suite = Suite::load(options)?;
+        if let Err(mut invalid) = validate(&suite) {
+            // Orphaned files usually explain why a test is missing
+            invalid.problems.extend(suite.warnings);
+            return Err(invalid);
+        }
+
+        let checkstyle = Checkstyle::load(&suite.test_path);
+        Ok(Self {
+            suite,
+            checkstyle,
+            events: EventBus::new(),
+        })
+    }
+
+    /// Receives the events of everything run from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.events.subscribe()
+    }
+
+    /// Runs `make build`
+    pub async fn build(&self) -> Result<String, BuildError> {
+        build(&self.events).await
+    }
+
+    /// Runs every test of the suite
+    pub async fn run(&mut self, options: &RunOptions, on_event: impl FnMut(Event<'_>)) {
+        let suite = &mut self.suite;
+        suite.valgrind_enabled = options.valgrind != Valgrind::Off;
+
+        run_tests(
+            &mut suite.tests,
+            &suite.exec_name,
+            &suite.test_path,
+            options,
+            &self.events,
+            on_event,
+        )
+        .await;
+    }
+
+    /// Runs the coding style checker, keeping its findings for the score
+    pub async fn run_checkstyle(&mut self) -> io::Result<&Checkstyle> {
+        self.checkstyle = checkstyle(&self.suite.test_path, &self.events).await?;
+
+        Ok(&self.checkstyle)
+    }
+
+    pub fn results(&self) -> Results {
+        Results::from_tests(
+            &self.suite.tests,
+            &self.suite.exec_name,
+            self.suite.valgrind_enabled,
+        )
+    }
+
+    /// The final score and the subtotals, following the rules of the suite
+    pub fn score_breakdown(&self) -> ScoreBreakdown {
+        self.suite.scoring.breakdown(
+            &self.suite.groups,
+            &self.suite.exec_name,
+            &self.suite.tests,
+            self.checkstyle.problems(),
+        )
+    }
+
+    /// The current results, to be stored in the history
+    pub fn history_run(&self) -> UnstoredRun {
+        UnstoredRun::new(
+            &self.suite.exec_name,
+            &self.suite.tests,
+            self.suite.valgrind_enabled,
+            &self.suite.scoring,
+            &self.score_breakdown(),
+        )
+    }
+}
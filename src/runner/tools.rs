@@ -0,0 +1,124 @@
+use std::fmt::{self, Display};
+use std::io;
+
+use tokio::fs;
+use tokio::process::Command;
+
+use super::{EventBus, ProgressEvent};
+
+/// Why the homework could not be built
+#[derive(Debug)]
+pub enum BuildError {
+    /// `make` could not be started
+    Spawn(io::Error),
+    Failed {
+        stdout: String,
+        stderr: String,
+    },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(err) => write!(f, "Cannot run make: {}", err),
+            Self::Failed { stdout, stderr } => {
+                write!(
+                    f,
+                    "Makefile error or makefile not found\n{}{}",
+                    stdout, stderr
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Runs `make build`, returning what it printed
+pub async fn build(events: &EventBus) -> Result<String, BuildError> {
+    events.publish(ProgressEvent::BuildStarted);
+
+    let result = run_make().await;
+    events.publish(ProgressEvent::BuildFinished {
+        success: result.is_ok(),
+        output: match &result {
+            Ok(output) => output.clone(),
+            Err(err) => err.to_string(),
+        },
+    });
+
+    result
+}
+
+async fn run_make() -> Result<String, BuildError> {
+    let output = Command::new("make")
+        .arg("build")
+        .output()
+        .await
+        .map_err(BuildError::Spawn)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if output.status.code().is_some_and(|code| code != 0) {
+        return Err(BuildError::Failed {
+            stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        });
+    }
+
+    Ok(stdout)
+}
+
+/// The findings of the coding style checker
+#[derive(Debug, Clone, Default)]
+pub struct Checkstyle {
+    pub output: String,
+    /// Number of checks, warnings and errors
+    pub errors: Vec<i32>,
+}
+
+impl Checkstyle {
+    pub fn parse(output: String) -> Self {
+        let mut errors = vec![0, 0, 0];
+
+        output.lines().for_each(|line| {
+            match line {
+                _ if line.contains("CHECK") => errors[0] += 1,
+                _ if line.contains("WARNING") => errors[1] += 1,
+                _ if line.contains("ERROR") => errors[2] += 1,
+                _ => (),
+            };
+        });
+
+        Self { output, errors }
+    }
+
+    /// Checks, warnings and errors together
+    pub fn problems(&self) -> usize {
+        self.errors.iter().sum::<i32>().max(0) as usize
+    }
+
+    /// The last findings, saved in the test path
+    pub fn load(test_path: &str) -> Self {
+        Self::parse(
+            std::fs::read_to_string(format!("{}checkstyle.txt", test_path)).unwrap_or_default(),
+        )
+    }
+}
+
+/// Runs `cs/cs.sh` from the test path and saves its findings next to it
+pub async fn checkstyle(test_path: &str, events: &EventBus) -> io::Result<Checkstyle> {
+    let output = Command::new(format!("{}/cs/cs.sh", test_path))
+        .arg(".")
+        .output()
+        .await?
+        .stdout;
+
+    fs::write(format!("{}checkstyle.txt", test_path), &output).await?;
+
+    let checkstyle = Checkstyle::parse(String::from_utf8_lossy(&output).into_owned());
+    events.publish(ProgressEvent::CheckstyleFinished {
+        problems: checkstyle.problems(),
+    });
+
+    Ok(checkstyle)
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use eyre::{Result, WrapErr};
+use serde::Deserialize;
+
+/// Preferences of the user, shared by every homework. They are read from
+/// `hw_checker/config.toml` in the configuration directory, never from the homework.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub tools: Tools,
+    /// Keys of the actions, by the name of the action
+    pub keys: BTreeMap<String, KeyBindings>,
+}
+
+/// One key or a list of keys, an empty list leaves the action without a key
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum KeyBindings {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyBindings {
+    pub fn keys(&self) -> &[String] {
+        match self {
+            Self::One(key) => std::slice::from_ref(key),
+            Self::Many(keys) => keys,
+        }
+    }
+}
+
+/// Programs the checker hands the terminal to, given as a command line whose arguments
+/// are split on whitespace
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tools {
+    /// Compares two files, run with the ref and the output after its arguments
+    pub diff: String,
+    /// Opens a file, `$VISUAL` or `$EDITOR` when not set
+    pub editor: Option<String>,
+}
+
+impl Default for Tools {
+    fn default() -> Self {
+        Self {
+            diff: String::from("vimdiff"),
+            editor: None,
+        }
+    }
+}
+
+impl Tools {
+    pub fn editor(&self) -> String {
+        self.editor
+            .clone()
+            .or_else(|| env::var("VISUAL").ok())
+            .or_else(|| env::var("EDITOR").ok())
+            .filter(|editor| !editor.trim().is_empty())
+            .unwrap_or_else(|| String::from("vi"))
+    }
+}
+
+impl Settings {
+    /// `$XDG_CONFIG_HOME/hw_checker/config.toml`, or `~/.config/hw_checker/config.toml`
+    pub fn path() -> Option<PathBuf> {
+        let directory = match env::var_os("XDG_CONFIG_HOME") {
+            Some(directory) if !directory.is_empty() => PathBuf::from(directory),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+
+        Some(directory.join("hw_checker").join("config.toml"))
+    }
+
+    /// The settings of the user, the defaults when there is no configuration file
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("Cannot read {}", path.display()))
+            }
+        };
+
+        toml::from_str(&content).wrap_err_with(|| format!("Cannot parse {}", path.display()))
+    }
+}
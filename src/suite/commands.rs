@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+/// Lines of the ref printed by each command of the input, the first word of its line
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct OutputMap(pub HashMap<String, usize>);
+
+impl OutputMap {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The lines of the ref printed by every line of the input, up to the first
+    /// command missing from the map, after which the lines cannot be told apart
+    pub fn blocks(&self, input: &str) -> Vec<Range<usize>> {
+        let mut blocks = Vec::new();
+        let mut start = 0;
+
+        for line in input.lines() {
+            let lines = match line.split_whitespace().next() {
+                Some(command) => match self.0.get(command) {
+                    Some(&lines) => lines,
+                    None => break,
+                },
+                None => 0,
+            };
+
+            blocks.push(start..start + lines);
+            start += lines;
+        }
+
+        blocks
+    }
+
+    /// The line of the input that printed this line of the ref, both from 0
+    pub fn command_at(&self, input: &str, line: usize) -> Option<usize> {
+        self.blocks(input)
+            .iter()
+            .position(|block| block.contains(&line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(commands: &[(&str, usize)]) -> OutputMap {
+        OutputMap(
+            commands
+                .iter()
+                .map(|(command, lines)| (command.to_string(), *lines))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn every_command_prints_a_block() {
+        let map = map(&[("add", 0), ("print", 2), ("count", 1)]);
+        let input = "add a\nprint\n\ncount\nprint all\n";
+
+        assert_eq!(map.blocks(input), [0..0, 0..2, 2..2, 2..3, 3..5]);
+        assert_eq!(map.command_at(input, 0), Some(1));
+        assert_eq!(map.command_at(input, 2), Some(3));
+        assert_eq!(map.command_at(input, 4), Some(4));
+        assert_eq!(map.command_at(input, 5), None);
+    }
+
+    #[test]
+    fn stops_at_an_unknown_command() {
+        let map = map(&[("print", 1)]);
+        let input = "print\nprint\nsort\nprint\n";
+
+        assert_eq!(map.blocks(input), [0..1, 1..2]);
+        assert_eq!(map.command_at(input, 2), None);
+        assert!(OutputMap::default().blocks(input).is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The tests found on disk, following the `NN-exec.in` / `NN-exec.ref` convention
+#[derive(Debug, Clone, Default)]
+pub struct Discovered {
+    /// Test ids that have both an input and a ref, grouped by executable
+    pub execs: BTreeMap<String, Vec<usize>>,
+    pub orphans: Vec<Orphan>,
+}
+
+/// A file that has no counterpart in the other directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Orphan {
+    MissingRef { exec: String, id: usize },
+    MissingInput { exec: String, id: usize },
+}
+
+impl Display for Orphan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Orphan::MissingRef { exec, id } => {
+                write!(f, "input/{:02}-{}.in has no matching ref file", id, exec)
+            }
+            Orphan::MissingInput { exec, id } => {
+                write!(f, "ref/{:02}-{}.ref has no matching input file", id, exec)
+            }
+        }
+    }
+}
+
+impl Discovered {
+    pub fn ids(&self, exec: &str) -> Option<&[usize]> {
+        self.execs.get(exec).map(Vec::as_slice)
+    }
+
+    /// Keeps the inputs that have no ref as tests, for their ref to be written
+    pub fn pair_missing_refs(&mut self) {
+        self.orphans.retain(|orphan| match orphan {
+            Orphan::MissingRef { exec, id } => {
+                let ids = self.execs.entry(exec.clone()).or_default();
+                ids.push(*id);
+                ids.sort_unstable();
+                false
+            }
+            Orphan::MissingInput { .. } => true,
+        });
+    }
+}
+
+/// Scans `test_path/input` and `test_path/ref` and pairs the files by id and
+/// executable name
+pub fn discover(test_path: &str) -> io::Result<Discovered> {
+    let inputs = scan(&Path::new(test_path).join("input"), "in")?;
+    let refs = scan(&Path::new(test_path).join("ref"), "ref")?;
+
+    let mut discovered = Discovered::default();
+
+    for (exec, ids) in inputs.iter() {
+        let empty = BTreeSet::new();
+        let ref_ids = refs.get(exec).unwrap_or(&empty);
+
+        for id in ids.difference(ref_ids) {
+            discovered.orphans.push(Orphan::MissingRef {
+                exec: exec.clone(),
+                id: *id,
+            });
+        }
+
+        let paired: Vec<usize> = ids.intersection(ref_ids).copied().collect();
+        if !paired.is_empty() {
+            discovered.execs.insert(exec.clone(), paired);
+        }
+    }
+
+    for (exec, ids) in refs.iter() {
+        let empty = BTreeSet::new();
+        let input_ids = inputs.get(exec).unwrap_or(&empty);
+
+        for id in ids.difference(input_ids) {
+            discovered.orphans.push(Orphan::MissingInput {
+                exec: exec.clone(),
+                id: *id,
+            });
+        }
+    }
+
+    Ok(discovered)
+}
+
+/// Collects the ids of all the `NN-exec.<extension>` files in `dir`
+fn scan(dir: &Path, extension: &str) -> io::Result<BTreeMap<String, BTreeSet<usize>>> {
+    let mut found: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.extension().and_then(|ext| ext.to_str()) != Some(extension) {
+            continue;
+        }
+
+        if let Some((id, exec)) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(parse_stem)
+        {
+            found.entry(exec).or_default().insert(id);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Splits `NN-exec` into the test id and the executable name
+fn parse_stem(stem: &str) -> Option<(usize, String)> {
+    let (id, exec) = stem.split_once('-')?;
+
+    if exec.is_empty() {
+        return None;
+    }
+
+    Some((id.parse().ok()?, exec.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A test directory holding empty files with these names
+    fn test_dir(name: &str, inputs: &[&str], refs: &[&str]) -> String {
+        let dir = std::env::temp_dir().join(format!("hw_checker-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        for (sub, files) in [("input", inputs), ("ref", refs)] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+            for file in files {
+                fs::write(dir.join(sub).join(file), "").unwrap();
+            }
+        }
+
+        dir.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn pairs_inputs_and_refs() {
+        let dir = test_dir(
+            "pairs",
+            &[
+                "00-feed.in",
+                "01-feed.in",
+                "02-feed.in",
+                "00-query.in",
+                "notes.txt",
+            ],
+            &[
+                "00-feed.ref",
+                "01-feed.ref",
+                "03-feed.ref",
+                "00-query.ref",
+                "x-feed.ref",
+            ],
+        );
+        let mut discovered = discover(&dir).unwrap();
+
+        assert_eq!(discovered.ids("feed"), Some(&[0, 1][..]));
+        assert_eq!(discovered.ids("query"), Some(&[0][..]));
+        assert_eq!(
+            discovered.orphans,
+            [
+                Orphan::MissingRef {
+                    exec: String::from("feed"),
+                    id: 2
+                },
+                Orphan::MissingInput {
+                    exec: String::from("feed"),
+                    id: 3
+                },
+            ]
+        );
+
+        discovered.pair_missing_refs();
+        assert_eq!(discovered.ids("feed"), Some(&[0, 1, 2][..]));
+        assert_eq!(discovered.orphans.len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn parses_the_file_names() {
+        assert_eq!(parse_stem("07-feed"), Some((7, String::from("feed"))));
+        assert_eq!(
+            parse_stem("07-two-words"),
+            Some((7, String::from("two-words")))
+        );
+        assert_eq!(parse_stem("07-"), None);
+        assert_eq!(parse_stem("feed"), None);
+        assert_eq!(parse_stem("x-feed"), None);
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use super::Test;
+
+/// A set of tests that can be run together, with its own subtotal.
+///
+/// Every executable is a group, and the manifest can split it in nested
+/// sub-tasks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub name: String,
+    pub exec: String,
+    pub ids: Vec<usize>,
+    /// 0 for an executable, 1 for its sub-tasks and so on
+    pub depth: usize,
+    /// Multiplies the subtotal
+    pub weight: f64,
+    /// Highest subtotal, after the weight is applied
+    pub cap: Option<f64>,
+}
+
+/// A sub-task of an executable, as written in the manifest
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GroupManifest {
+    pub name: String,
+    /// Ids of the tests in this group, besides the ones of the nested groups
+    #[serde(default)]
+    pub tests: Vec<usize>,
+    #[serde(default)]
+    pub groups: Vec<GroupManifest>,
+    pub weight: Option<f64>,
+    pub cap: Option<f64>,
+}
+
+impl Group {
+    /// The group of a whole executable
+    pub fn from_exec(exec: &str, tests: &[Test]) -> Self {
+        Self {
+            name: exec.to_string(),
+            exec: exec.to_string(),
+            ids: tests.iter().map(|test| test.id).collect(),
+            depth: 0,
+            weight: 1.0,
+            cap: None,
+        }
+    }
+
+    /// Flattens the nested groups of the manifest, parents before children.
+    ///
+    /// Ids without a test are dropped and reported in `warnings`.
+    pub fn flatten(
+        exec: &str,
+        manifests: &[GroupManifest],
+        tests: &[Test],
+        depth: usize,
+        warnings: &mut Vec<String>,
+    ) -> Vec<Self> {
+        let mut groups = Vec::new();
+
+        for manifest in manifests {
+            let children = Self::flatten(exec, &manifest.groups, tests, depth + 1, warnings);
+
+            let mut ids = Vec::new();
+            for id in manifest.tests.iter() {
+                if tests.iter().any(|test| test.id == *id) {
+                    ids.push(*id);
+                } else {
+                    warnings.push(format!(
+                        "Group {} of {} contains test {:02}, which does not exist",
+                        manifest.name, exec, id
+                    ));
+                }
+            }
+            for child in children.iter().filter(|child| child.depth == depth + 1) {
+                ids.extend(child.ids.iter().copied());
+            }
+            ids.sort_unstable();
+            ids.dedup();
+
+            groups.push(Self {
+                name: manifest.name.clone(),
+                exec: exec.to_string(),
+                ids,
+                depth,
+                weight: manifest.weight.unwrap_or(1.0),
+                cap: manifest.cap,
+            });
+            groups.extend(children);
+        }
+
+        groups
+    }
+
+    /// Positions of the group's tests in the list of its executable
+    pub fn positions(&self, tests: &[Test]) -> Vec<usize> {
+        tests
+            .iter()
+            .enumerate()
+            .filter(|(_, test)| self.ids.contains(&test.id))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
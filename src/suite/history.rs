@@ -0,0 +1,459 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{ScoreBreakdown, Scoring, Test, TestStatus};
+
+/// Older runs are dropped, so the file does not grow forever
+const MAX_RUNS: usize = 100;
+
+/// Every full run, oldest first, owned by the checker
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct History {
+    #[serde(default)]
+    pub runs: Vec<HistoryRun>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryRun {
+    /// Seconds since the unix epoch
+    pub timestamp: i64,
+    /// Short hash of the commit checked out in the homework, if it is a git repository
+    pub commit: Option<String>,
+    pub score: f64,
+    pub max_score: f64,
+    pub tests: Vec<HistoryTest>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct HistoryTest {
+    pub exec: String,
+    pub id: usize,
+    pub status: TestStatus,
+    pub time: f64,
+    pub score: f64,
+    /// File holding the output, named after its SHA-256 so equal outputs are stored once
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+impl History {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {}", path))?;
+
+        serde_json::from_str(&content).wrap_err_with(|| format!("Cannot parse {}", path))
+    }
+
+    /// Writes the runs to `path` and removes the outputs in `outputs` that no run
+    /// refers to anymore
+    pub fn save(&self, path: &str, outputs: &str) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("Cannot write {}", path))?;
+
+        let used: HashSet<&str> = self
+            .runs
+            .iter()
+            .flat_map(|run| run.tests.iter())
+            .filter_map(|test| test.output.as_deref())
+            .collect();
+
+        if let Ok(entries) = fs::read_dir(outputs) {
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                if !used.contains(name.to_string_lossy().as_ref()) {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn push(&mut self, run: HistoryRun) {
+        self.runs.push(run);
+
+        if self.runs.len() > MAX_RUNS {
+            self.runs.drain(..self.runs.len() - MAX_RUNS);
+        }
+    }
+
+    /// The run the current results are compared to: the one before the last while
+    /// they are the results of the last run, and the last one once they changed
+    pub fn previous(&self, current_is_last: bool) -> Option<&HistoryRun> {
+        self.runs.iter().rev().nth(usize::from(current_is_last))
+    }
+
+    /// Every test has the status and the score it got in the last run
+    pub fn is_last(&self, exec_name: &[String], tests: &[Vec<Test>], scoring: &Scoring) -> bool {
+        let Some(last) = self.runs.last() else {
+            return false;
+        };
+
+        exec_name.iter().zip(tests.iter()).all(|(exec, tests)| {
+            tests.iter().all(|test| {
+                last.test(exec, test.id).is_some_and(|recorded| {
+                    recorded.status == test.status && recorded.score == scoring.test_points(test)
+                })
+            })
+        })
+    }
+
+    /// Pairs the results of every test in the `before` and `after` runs
+    pub fn compare(&self, before: usize, after: usize) -> Vec<TestChange<'_>> {
+        let (Some(before), Some(after)) = (self.runs.get(before), self.runs.get(after)) else {
+            return Vec::new();
+        };
+
+        let mut changes: Vec<TestChange> = before
+            .tests
+            .iter()
+            .map(|test| TestChange {
+                exec: &test.exec,
+                id: test.id,
+                before: Some(test),
+                after: after.test(&test.exec, test.id),
+            })
+            .collect();
+
+        // Tests added after the first run
+        changes.extend(
+            after
+                .tests
+                .iter()
+                .filter(|test| before.test(&test.exec, test.id).is_none())
+                .map(|test| TestChange {
+                    exec: &test.exec,
+                    id: test.id,
+                    before: None,
+                    after: Some(test),
+                }),
+        );
+
+        changes
+    }
+
+    /// The result of a test in every run, `None` where it was not run
+    pub fn timeline(&self, exec: &str, id: usize) -> Vec<Option<&HistoryTest>> {
+        self.runs.iter().map(|run| run.test(exec, id)).collect()
+    }
+}
+
+/// A run taken from the current results, with the outputs of its tests not stored yet
+#[derive(Debug, Clone)]
+pub struct UnstoredRun {
+    run: HistoryRun,
+    /// The output of every test of the run, in the same order
+    logs: Vec<String>,
+}
+
+impl UnstoredRun {
+    /// Records the current results of the tests
+    pub fn new(
+        exec_name: &[String],
+        tests: &[Vec<Test>],
+        valgrind_enabled: bool,
+        scoring: &Scoring,
+        breakdown: &ScoreBreakdown,
+    ) -> Self {
+        let tests = exec_name
+            .iter()
+            .zip(tests.iter())
+            .flat_map(|(exec, tests)| {
+                tests.iter().map(move |test| {
+                    let recorded = HistoryTest {
+                        exec: exec.clone(),
+                        id: test.id,
+                        status: test.status.clone(),
+                        time: test.time(valgrind_enabled),
+                        score: scoring.test_points(test),
+                        output: None,
+                    };
+
+                    (recorded, test.log.clone())
+                })
+            });
+        let (tests, logs) = tests.unzip();
+
+        let run = HistoryRun {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs() as i64)
+                .unwrap_or(0),
+            commit: None,
+            score: breakdown.total,
+            max_score: breakdown.max,
+            tests,
+        };
+
+        Self { run, logs }
+    }
+
+    /// Stores the outputs in `outputs` and finds the current commit. It writes files
+    /// and runs git, so it is done outside of the app.
+    pub fn store(self, outputs: &str) -> HistoryRun {
+        let Self { mut run, logs } = self;
+
+        for (test, log) in run.tests.iter_mut().zip(logs.iter()) {
+            test.output = store_output(outputs, log);
+        }
+        run.commit = current_commit();
+
+        run
+    }
+}
+
+impl HistoryRun {
+    pub fn test(&self, exec: &str, id: usize) -> Option<&HistoryTest> {
+        self.tests
+            .iter()
+            .find(|test| test.exec == exec && test.id == id)
+    }
+
+    /// The local date and time of the run
+    pub fn date(&self) -> String {
+        use chrono::{Local, TimeZone};
+
+        match Local.timestamp_opt(self.timestamp, 0).single() {
+            Some(date) => date.format("%Y-%m-%d %H:%M").to_string(),
+            None => self.timestamp.to_string(),
+        }
+    }
+}
+
+/// The results of a test in two runs
+#[derive(Debug, Clone, Copy)]
+pub struct TestChange<'a> {
+    pub exec: &'a str,
+    pub id: usize,
+    pub before: Option<&'a HistoryTest>,
+    pub after: Option<&'a HistoryTest>,
+}
+
+impl TestChange<'_> {
+    pub fn status_changed(&self) -> bool {
+        self.before.map(|test| &test.status) != self.after.map(|test| &test.status)
+    }
+
+    /// Both runs have the output stored, and it is not the same
+    pub fn output_changed(&self) -> bool {
+        match (self.before, self.after) {
+            (Some(before), Some(after)) => match (&before.output, &after.output) {
+                (Some(before), Some(after)) => before != after,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Seconds gained or lost by the second run
+    pub fn time_delta(&self) -> Option<f64> {
+        Some(self.after?.time - self.before?.time)
+    }
+}
+
+impl HistoryTest {
+    /// The output of the test in this run, read from `outputs`
+    pub fn read_output(&self, outputs: &str) -> Option<String> {
+        fs::read_to_string(Path::new(outputs).join(self.output.as_ref()?)).ok()
+    }
+}
+
+/// Writes the output in `outputs`, unless an equal one is already there, and
+/// returns its file name
+fn store_output(outputs: &str, output: &str) -> Option<String> {
+    let hash: String = Sha256::digest(output.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    // A different file with the same name was written by hand or cut short, so
+    // another name is used instead of pointing to it
+    for attempt in 0.. {
+        let name = match attempt {
+            0 => format!("{}.out", hash),
+            attempt => format!("{}-{}.out", hash, attempt),
+        };
+
+        let path = Path::new(outputs).join(&name);
+        match fs::read(&path) {
+            Ok(stored) if stored == output.as_bytes() => return Some(name),
+            Ok(_) => continue,
+            Err(_) => {
+                fs::create_dir_all(outputs).ok()?;
+                fs::write(path, output).ok()?;
+                return Some(name);
+            }
+        }
+    }
+
+    None
+}
+
+/// Short hash of `HEAD`, `None` outside of a git repository
+fn current_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let commit = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!commit.is_empty()).then_some(commit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(tests: &[(&str, usize, TestStatus, &str)]) -> HistoryRun {
+        HistoryRun {
+            timestamp: 0,
+            commit: None,
+            score: 0.0,
+            max_score: 0.0,
+            tests: tests
+                .iter()
+                .map(|(exec, id, status, output)| HistoryTest {
+                    exec: exec.to_string(),
+                    id: *id,
+                    status: status.clone(),
+                    time: *id as f64,
+                    score: status.points(),
+                    output: Some(output.to_string()),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn compares_two_runs() {
+        let passed = TestStatus::Passed { points: 5.0 };
+        let history = History {
+            runs: vec![
+                run(&[
+                    ("feed", 0, passed.clone(), "a.out"),
+                    ("feed", 1, TestStatus::Failed, "b.out"),
+                    ("feed", 2, TestStatus::Timeout, "c.out"),
+                ]),
+                run(&[
+                    ("feed", 0, passed.clone(), "a.out"),
+                    ("feed", 1, passed, "d.out"),
+                    ("query", 0, TestStatus::Failed, "e.out"),
+                ]),
+            ],
+        };
+
+        let changes = history.compare(0, 1);
+        let summary: Vec<(&str, usize, bool, bool, bool, bool)> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.exec,
+                    change.id,
+                    change.before.is_some(),
+                    change.after.is_some(),
+                    change.status_changed(),
+                    change.output_changed(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("feed", 0, true, true, false, false),
+                ("feed", 1, true, true, true, true),
+                // Removed, then added after the first run
+                ("feed", 2, true, false, true, false),
+                ("query", 0, false, true, true, false),
+            ]
+        );
+        assert_eq!(changes[0].time_delta(), Some(0.0));
+        assert_eq!(changes[2].time_delta(), None);
+
+        assert!(history.compare(0, 2).is_empty());
+        // Only the last run has the query tests
+        let has_query = |run: &HistoryRun| run.test("query", 0).is_some();
+        assert_eq!(history.previous(true).map(has_query), Some(false));
+        assert_eq!(history.previous(false).map(has_query), Some(true));
+        assert_eq!(history.timeline("feed", 2).len(), 2);
+        assert!(history.timeline("feed", 2)[1].is_none());
+    }
+
+    #[test]
+    fn knows_when_the_results_are_the_last_run() {
+        let passed = TestStatus::Passed { points: 5.0 };
+        let history = History {
+            runs: vec![run(&[
+                ("feed", 0, passed.clone(), "a.out"),
+                ("feed", 1, TestStatus::Failed, "b.out"),
+            ])],
+        };
+        let test = |id, status| Test {
+            id,
+            name: format!("Test {:02}", id),
+            status,
+            log: String::new(),
+            time_normal: 0.0,
+            time_valgrind: 0.0,
+            passed_output: false,
+            timeout: 1000,
+            test_score: 5,
+        };
+        let exec_name = [String::from("feed")];
+        let scoring = Scoring::default();
+
+        let mut tests = vec![vec![test(0, passed.clone()), test(1, TestStatus::Failed)]];
+        assert!(history.is_last(&exec_name, &tests, &scoring));
+
+        // Run again, the test now passes
+        tests[0][1].status = passed;
+        assert!(!history.is_last(&exec_name, &tests, &scoring));
+        assert!(!History::default().is_last(&exec_name, &tests, &scoring));
+    }
+
+    #[test]
+    fn keeps_the_latest_runs() {
+        let mut history = History::default();
+        for timestamp in 0..MAX_RUNS as i64 + 5 {
+            let mut run = run(&[]);
+            run.timestamp = timestamp;
+            history.push(run);
+        }
+
+        assert_eq!(history.runs.len(), MAX_RUNS);
+        assert_eq!(history.runs[0].timestamp, 5);
+    }
+
+    #[test]
+    fn equal_outputs_are_stored_once() {
+        let dir = std::env::temp_dir().join(format!("hw_checker-history-{}", std::process::id()));
+        let outputs = dir.to_string_lossy().into_owned();
+        let _ = fs::remove_dir_all(&dir);
+
+        let name = store_output(&outputs, "1 2 3\n").unwrap();
+        // The SHA-256 of the output
+        assert_eq!(
+            name,
+            "1def07dbe06eeb097aafec8a40329937cd20c93a83634b8221ea2b41a894310c.out"
+        );
+        assert_eq!(store_output(&outputs, "1 2 3\n").unwrap(), name);
+        assert_ne!(store_output(&outputs, "1 2 4\n").unwrap(), name);
+
+        // A file changed by hand is not pointed to by other outputs
+        fs::write(dir.join(&name), "changed").unwrap();
+        let other = store_output(&outputs, "1 2 3\n").unwrap();
+        assert_ne!(other, name);
+        assert_eq!(fs::read_to_string(dir.join(&other)).unwrap(), "1 2 3\n");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,90 @@
+use std::fs;
+
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use super::{GroupManifest, OutputMap, Scoring};
+
+/// Time limit of a test that does not set one, in milliseconds
+pub const DEFAULT_TIMEOUT: u64 = 30000;
+
+/// The test suite as described by the course, it is never written by the checker
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Manifest {
+    /// Directory holding `input/`, `ref/` and `output/`
+    pub test_path: String,
+    /// Values used by the tests that do not set their own
+    #[serde(default)]
+    pub defaults: SuiteDefaults,
+    #[serde(default)]
+    pub exec: Vec<ExecManifest>,
+    #[serde(default)]
+    pub scoring: Scoring,
+}
+
+/// An executable built by the homework and the metadata of its tests.
+///
+/// Tests are discovered from the files on disk, so they only need to be listed
+/// here to give them a name, a score or a different timeout.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExecManifest {
+    pub name: String,
+    #[serde(default)]
+    pub defaults: TestDefaults,
+    #[serde(default)]
+    pub tests: Vec<TestManifest>,
+    /// Sub-tasks of the executable, each with its own subtotal
+    #[serde(default)]
+    pub groups: Vec<GroupManifest>,
+    /// Multiplies the subtotal of the executable
+    pub weight: Option<f64>,
+    /// Highest subtotal of the executable, after the weight is applied
+    pub cap: Option<f64>,
+    /// Lines printed by each command of the inputs, to show which command printed a
+    /// line of the output
+    #[serde(default)]
+    pub commands: OutputMap,
+}
+
+/// The `[defaults]` of the suite, the ones of the tests and the ones of the whole run
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SuiteDefaults {
+    #[serde(flatten)]
+    pub tests: TestDefaults,
+    /// Only the tests with a lower id run under valgrind, all of them when it is not set
+    pub valgrind_below: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TestDefaults {
+    pub timeout: Option<u64>,
+    pub score: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TestManifest {
+    pub id: usize,
+    pub name: Option<String>,
+    /// Time limit in milliseconds
+    pub timeout: Option<u64>,
+    #[serde(alias = "test_score")]
+    pub score: Option<usize>,
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {}", path))?;
+
+        toml::from_str(&content).wrap_err_with(|| format!("Cannot parse {}", path))
+    }
+
+    pub fn exec(&self, name: &str) -> Option<&ExecManifest> {
+        self.exec.iter().find(|exec| exec.name == name)
+    }
+}
+
+impl ExecManifest {
+    pub fn test(&self, id: usize) -> Option<&TestManifest> {
+        self.tests.iter().find(|test| test.id == id)
+    }
+}
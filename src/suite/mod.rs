@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::Path;
+
+use eyre::{Result, WrapErr};
+
+pub mod commands;
+pub mod discover;
+pub mod group;
+pub mod history;
+pub mod manifest;
+pub mod results;
+pub mod scoring;
+pub mod status;
+pub mod test;
+pub mod validate;
+
+pub use self::commands::OutputMap;
+pub use self::discover::{discover, Discovered, Orphan};
+pub use self::group::{Group, GroupManifest};
+pub use self::history::{History, HistoryRun, HistoryTest, TestChange, UnstoredRun};
+pub use self::manifest::{Manifest, DEFAULT_TIMEOUT};
+pub use self::results::Results;
+pub use self::scoring::{format_points, ScoreBreakdown, ScoreRow, Scoring};
+pub use self::status::TestStatus;
+pub use self::test::Test;
+pub use self::validate::{validate, InvalidSuite};
+
+/// Read-only test suite definition, shipped together with the tests
+pub const MANIFEST_PATH: &str = "./suite.toml";
+/// Results owned by the checker, rewritten after every run
+pub const RESULTS_PATH: &str = "./results.json";
+/// Every full run, to follow the score over time
+pub const HISTORY_PATH: &str = "./history.json";
+/// Outputs of the tests in the recorded runs
+pub const HISTORY_OUTPUTS_PATH: &str = "./history/";
+/// Old single-file format, mixing definitions and results
+pub const LEGACY_DB_PATH: &str = "./data.json";
+/// The tests of a legacy suite run under valgrind below this id, the later ones are
+/// usually too slow for it
+pub const LEGACY_VALGRIND_BELOW: usize = 13;
+
+/// Overrides of the suite, given on the command line
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Path of the manifest, a `.json` file is read as a legacy `data.json`
+    pub manifest: Option<String>,
+    pub test_path: Option<String>,
+    /// Keeps only the tests whose `NN-exec` or name contain this
+    pub filter: Option<String>,
+    pub valgrind: Option<bool>,
+    /// Multiplies the timeout of every test
+    pub timeout_scale: Option<f64>,
+}
+
+/// A test suite with the results of the previous runs merged in
+#[derive(Debug, Clone)]
+pub struct Suite {
+    pub tests: Vec<Vec<Test>>,
+    pub test_path: String,
+    pub exec_name: Vec<String>,
+    pub valgrind_enabled: bool,
+    /// Only the tests with a lower id run under valgrind in `run`
+    pub valgrind_below: Option<usize>,
+    /// The executables and their sub-tasks, parents before children
+    pub groups: Vec<Group>,
+    pub scoring: Scoring,
+    /// The commands of the inputs of the executables that describe them
+    pub commands: HashMap<String, OutputMap>,
+    /// Problems found while loading, that do not stop the checker
+    pub warnings: Vec<String>,
+    /// Loaded from a `data.json`, where the tests are written by hand
+    pub legacy: bool,
+}
+
+impl Suite {
+    /// Loads the manifest and the stored results, then applies the overrides.
+    ///
+    /// Without a manifest path, the default one is used, falling back to the
+    /// legacy `data.json` when it does not exist.
+    pub fn load(options: &LoadOptions) -> Result<Self> {
+        let mut suite = match options.manifest.as_deref() {
+            Some(path) if path.ends_with(".json") => Self::load_legacy(path)?,
+            Some(path) => Self::load_from(path, RESULTS_PATH, options.test_path.as_deref())?,
+            None if !Path::new(MANIFEST_PATH).exists() && Path::new(LEGACY_DB_PATH).exists() => {
+                Self::load_legacy(LEGACY_DB_PATH)?
+            }
+            None => Self::load_from(MANIFEST_PATH, RESULTS_PATH, options.test_path.as_deref())?,
+        };
+
+        suite.apply(options);
+
+        Ok(suite)
+    }
+
+    /// Applies the command line overrides
+    pub fn apply(&mut self, options: &LoadOptions) {
+        if let Some(test_path) = options.test_path.as_ref() {
+            // Paths are built by appending to it
+            self.test_path = if test_path.ends_with('/') {
+                test_path.clone()
+            } else {
+                format!("{}/", test_path)
+            };
+        }
+
+        if let Some(valgrind) = options.valgrind {
+            self.valgrind_enabled = valgrind;
+        }
+
+        if let Some(scale) = options.timeout_scale {
+            for test in self.tests.iter_mut().flatten() {
+                test.timeout = (test.timeout as f64 * scale).round() as u64;
+            }
+        }
+
+        if let Some(filter) = options.filter.as_ref() {
+            let filter = filter.to_lowercase();
+            let exec_name = mem::take(&mut self.exec_name);
+            let tests = mem::take(&mut self.tests);
+
+            for (exec, mut list) in exec_name.into_iter().zip(tests) {
+                list.retain(|test| {
+                    format!("{:02}-{}", test.id, exec)
+                        .to_lowercase()
+                        .contains(&filter)
+                        || test.name.to_lowercase().contains(&filter)
+                });
+
+                if !list.is_empty() {
+                    self.exec_name.push(exec);
+                    self.tests.push(list);
+                }
+            }
+
+            let tests = &self.tests;
+            let exec_name = &self.exec_name;
+            self.groups.retain_mut(|group| {
+                let Some(exec) = exec_name.iter().position(|name| *name == group.exec) else {
+                    return false;
+                };
+                group
+                    .ids
+                    .retain(|id| tests[exec].iter().any(|test| test.id == *id));
+
+                !group.ids.is_empty()
+            });
+
+            if self.tests.is_empty() {
+                self.warnings
+                    .push(format!("No test matches the filter \"{}\"", filter));
+            }
+        }
+    }
+
+    /// Loads the manifest at `manifest_path`, discovers the tests on disk and
+    /// merges the results found at `results_path`, if any.
+    ///
+    /// The tests are discovered in `test_path` when given, instead of the one of
+    /// the manifest.
+    pub fn load_from(
+        manifest_path: &str,
+        results_path: &str,
+        test_path: Option<&str>,
+    ) -> Result<Self> {
+        let manifest = Manifest::load(manifest_path)?;
+        let results = if Path::new(results_path).exists() {
+            Results::load(results_path)?
+        } else {
+            Results::default()
+        };
+
+        let mut warnings = Vec::new();
+        let test_path = test_path.unwrap_or(&manifest.test_path);
+        let discovered = match discover(test_path) {
+            Ok(discovered) => Some(discovered),
+            Err(err) => {
+                warnings.push(format!(
+                    "Cannot discover tests in {}: {}, using only {}",
+                    test_path, err, manifest_path
+                ));
+                None
+            }
+        };
+
+        let mut suite = Self::merge(manifest, discovered.as_ref(), &results);
+        warnings.append(&mut suite.warnings);
+        suite.warnings = warnings;
+
+        Ok(suite)
+    }
+
+    /// Loads the tests of every input, including the ones that have no ref yet, for
+    /// their refs to be written. The stored results are left out.
+    pub fn load_inputs(options: &LoadOptions) -> Result<Self> {
+        let manifest_path = match options.manifest.as_deref() {
+            Some(path) => path,
+            None if !Path::new(MANIFEST_PATH).exists() && Path::new(LEGACY_DB_PATH).exists() => {
+                LEGACY_DB_PATH
+            }
+            None => MANIFEST_PATH,
+        };
+        // The tests of a legacy suite are listed, whether their refs exist or not
+        if manifest_path.ends_with(".json") {
+            let mut suite = Self::load_legacy(manifest_path)?;
+            suite.apply(options);
+            return Ok(suite);
+        }
+
+        let manifest = Manifest::load(manifest_path)?;
+        let test_path = options.test_path.as_deref().unwrap_or(&manifest.test_path);
+        let mut discovered = discover(test_path)
+            .wrap_err_with(|| format!("Cannot discover tests in {}", test_path))?;
+        discovered.pair_missing_refs();
+
+        let mut suite = Self::merge(manifest, Some(&discovered), &Results::default());
+        suite.apply(options);
+
+        Ok(suite)
+    }
+
+    /// Builds the suite from the discovered tests, taking their metadata from
+    /// the manifest and their status from the results, when they contain it.
+    ///
+    /// Without a discovery, the tests listed in the manifest are used.
+    pub fn merge(manifest: Manifest, discovered: Option<&Discovered>, results: &Results) -> Self {
+        let mut warnings = Vec::new();
+        let mut exec_name: Vec<String> =
+            manifest.exec.iter().map(|exec| exec.name.clone()).collect();
+
+        if let Some(discovered) = discovered {
+            warnings.extend(discovered.orphans.iter().map(Orphan::to_string));
+
+            for name in discovered.execs.keys() {
+                if !exec_name.contains(name) {
+                    exec_name.push(name.clone());
+                }
+            }
+        }
+
+        let mut tests = Vec::new();
+        let mut groups = Vec::new();
+        exec_name.retain(|name| {
+            let exec = manifest.exec(name);
+            let ids: Vec<usize> = match discovered {
+                Some(discovered) => {
+                    let ids = discovered.ids(name).unwrap_or_default();
+
+                    for test in exec.map(|exec| exec.tests.as_slice()).unwrap_or_default() {
+                        if !ids.contains(&test.id) {
+                            warnings.push(format!(
+                                "Test {:02}-{} is described in the manifest, but its input or ref is missing",
+                                test.id, name
+                            ));
+                        }
+                    }
+
+                    ids.to_vec()
+                }
+                None => exec
+                    .map(|exec| exec.tests.iter().map(|test| test.id).collect())
+                    .unwrap_or_default(),
+            };
+
+            if ids.is_empty() {
+                warnings.push(format!("Executable {} has no tests", name));
+                return false;
+            }
+
+            let list: Vec<Test> = ids
+                .into_iter()
+                .map(|id| {
+                    let meta = exec.and_then(|exec| exec.test(id));
+                    let defaults = exec.map(|exec| &exec.defaults);
+
+                    let mut merged = Test {
+                        id,
+                        name: meta
+                            .and_then(|meta| meta.name.clone())
+                            .unwrap_or_else(|| format!("Test {:02}", id)),
+                        status: TestStatus::Pending,
+                        log: String::new(),
+                        time_normal: 0.0,
+                        time_valgrind: 0.0,
+                        passed_output: false,
+                        timeout: meta
+                            .and_then(|meta| meta.timeout)
+                            .or(defaults.and_then(|defaults| defaults.timeout))
+                            .or(manifest.defaults.tests.timeout)
+                            .unwrap_or(DEFAULT_TIMEOUT),
+                        test_score: meta
+                            .and_then(|meta| meta.score)
+                            .or(defaults.and_then(|defaults| defaults.score))
+                            .or(manifest.defaults.tests.score)
+                            .unwrap_or(0),
+                    };
+
+                    if let Some(result) = results.get(name, id) {
+                        merged.status = result.status.clone().restored();
+                        merged.log = result.log.clone();
+                        merged.time_normal = result.time_normal;
+                        merged.time_valgrind = result.time_valgrind;
+                        merged.passed_output = result.passed_output;
+                    }
+
+                    merged
+                })
+                .collect();
+
+            let mut exec_group = Group::from_exec(name, &list);
+            if let Some(exec) = exec {
+                exec_group.weight = exec.weight.unwrap_or(1.0);
+                exec_group.cap = exec.cap;
+            }
+            groups.push(exec_group);
+            if let Some(exec) = exec {
+                groups.extend(Group::flatten(name, &exec.groups, &list, 1, &mut warnings));
+            }
+
+            tests.push(list);
+            true
+        });
+
+        let commands = manifest
+            .exec
+            .into_iter()
+            .filter(|exec| !exec.commands.is_empty())
+            .map(|exec| (exec.name, exec.commands))
+            .collect();
+
+        Self {
+            tests,
+            test_path: manifest.test_path,
+            exec_name,
+            valgrind_enabled: results.valgrind_enabled,
+            valgrind_below: manifest.defaults.valgrind_below,
+            groups,
+            scoring: manifest.scoring,
+            commands,
+            warnings,
+            legacy: false,
+        }
+    }
+
+    /// Reads a legacy `data.json`, which holds both the definitions and the
+    /// results
+    pub fn load_legacy(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {}", path))?;
+        let mut data: LegacyData =
+            serde_json::from_str(&content).wrap_err_with(|| format!("Cannot parse {}", path))?;
+        for test in data.tests.iter_mut().flatten() {
+            test.status = mem::take(&mut test.status).restored();
+        }
+
+        let groups = data
+            .exec_name
+            .iter()
+            .zip(data.tests.iter())
+            .map(|(exec, tests)| Group::from_exec(exec, tests))
+            .collect();
+
+        Ok(Self {
+            tests: data.tests,
+            test_path: data.test_path,
+            exec_name: data.exec_name,
+            groups,
+            scoring: Scoring::default(),
+            commands: HashMap::new(),
+            valgrind_enabled: data.valgrind_enabled,
+            valgrind_below: Some(LEGACY_VALGRIND_BELOW),
+            warnings: Vec::new(),
+            legacy: true,
+        })
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct LegacyData {
+    tests: Vec<Vec<Test>>,
+    test_path: String,
+    exec_name: Vec<String>,
+    valgrind_enabled: bool,
+}
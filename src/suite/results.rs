@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use eyre::{Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use super::{Test, TestStatus};
+
+/// The outcome of the last run of every test, owned by the checker
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Results {
+    #[serde(default = "valgrind_by_default")]
+    pub valgrind_enabled: bool,
+    /// Results grouped by executable name
+    #[serde(default)]
+    pub tests: BTreeMap<String, Vec<TestResult>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TestResult {
+    pub id: usize,
+    pub status: TestStatus,
+    #[serde(default)]
+    pub log: String,
+    #[serde(default)]
+    pub time_normal: f64,
+    #[serde(default)]
+    pub time_valgrind: f64,
+    #[serde(default)]
+    pub passed_output: bool,
+}
+
+/// Leaks are part of the score, so valgrind runs until it is disabled
+fn valgrind_by_default() -> bool {
+    true
+}
+
+impl Default for Results {
+    fn default() -> Self {
+        Self {
+            valgrind_enabled: valgrind_by_default(),
+            tests: BTreeMap::new(),
+        }
+    }
+}
+
+impl Results {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = fs::read_to_string(path).wrap_err_with(|| format!("Cannot read {}", path))?;
+
+        serde_json::from_str(&content).wrap_err_with(|| format!("Cannot parse {}", path))
+    }
+
+    /// Collects the mutable part of the tests
+    pub fn from_tests(tests: &[Vec<Test>], exec_name: &[String], valgrind_enabled: bool) -> Self {
+        let tests = tests
+            .iter()
+            .zip(exec_name)
+            .map(|(list, name)| {
+                let results = list
+                    .iter()
+                    .map(|test| TestResult {
+                        id: test.id,
+                        status: test.status.clone(),
+                        log: test.log.clone(),
+                        time_normal: test.time_normal,
+                        time_valgrind: test.time_valgrind,
+                        passed_output: test.passed_output,
+                    })
+                    .collect();
+
+                (name.clone(), results)
+            })
+            .collect();
+
+        Self {
+            valgrind_enabled,
+            tests,
+        }
+    }
+
+    /// Overwrites the results of the tests found in `other`, keeping the rest
+    pub fn update(&mut self, other: Results) {
+        self.valgrind_enabled = other.valgrind_enabled;
+
+        for (exec, results) in other.tests {
+            let stored = self.tests.entry(exec).or_default();
+
+            for result in results {
+                match stored.iter_mut().find(|stored| stored.id == result.id) {
+                    Some(stored) => *stored = result,
+                    None => stored.push(result),
+                }
+            }
+
+            stored.sort_by_key(|result| result.id);
+        }
+    }
+
+    pub fn get(&self, exec: &str, id: usize) -> Option<&TestResult> {
+        self.tests.get(exec)?.iter().find(|result| result.id == id)
+    }
+}
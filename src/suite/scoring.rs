@@ -0,0 +1,339 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Group, Test, TestStatus};
+
+/// How the final score is computed, as written in the `[scoring]` table of the
+/// manifest
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Scoring {
+    /// Score shown as the maximum, bonuses can go over it
+    pub max_score: f64,
+    pub checkstyle: CheckstyleRule,
+    pub valgrind: ValgrindRule,
+    /// Extra points given when the score reaches a threshold
+    pub bonus: Vec<BonusRule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CheckstyleRule {
+    pub points: f64,
+    /// The bonus is given while the checkstyle finds at most this many problems
+    pub max_errors: usize,
+    /// The bonus is given only when the tests are worth at least this much
+    pub min_tests_score: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ValgrindRule {
+    /// Part of the points given when the output is right, but valgrind finds
+    /// leaks or errors
+    pub leak_credit: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BonusRule {
+    pub name: String,
+    /// Score of the tests and checkstyle needed for the bonus
+    pub threshold: f64,
+    pub points: f64,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            max_score: 100.0,
+            checkstyle: CheckstyleRule::default(),
+            valgrind: ValgrindRule::default(),
+            bonus: Vec::new(),
+        }
+    }
+}
+
+impl Default for CheckstyleRule {
+    fn default() -> Self {
+        Self {
+            points: 10.0,
+            max_errors: 0,
+            min_tests_score: 0.0,
+        }
+    }
+}
+
+/// One row of the score breakdown
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct ScoreRow {
+    pub name: String,
+    /// Nesting of the group, 0 for executables and the other rows
+    pub depth: usize,
+    pub score: f64,
+    pub max: f64,
+}
+
+/// The final score, with the subtotal of every group, the checkstyle and the
+/// bonuses
+#[derive(Debug, Clone, Default)]
+pub struct ScoreBreakdown {
+    pub groups: Vec<ScoreRow>,
+    pub checkstyle: ScoreRow,
+    pub bonuses: Vec<ScoreRow>,
+    pub total: f64,
+    pub max: f64,
+}
+
+impl Scoring {
+    /// Points given for a single test
+    pub fn test_points(&self, test: &Test) -> f64 {
+        match test.status {
+            TestStatus::MemLeaks if test.passed_output => {
+                test.test_score as f64 * self.valgrind.leak_credit
+            }
+            ref status => status.points(),
+        }
+    }
+
+    /// Computes the subtotals of the groups, which are added together for every
+    /// executable, and the bonuses
+    pub fn breakdown(
+        &self,
+        groups: &[Group],
+        exec_name: &[String],
+        tests: &[Vec<Test>],
+        checkstyle_errors: usize,
+    ) -> ScoreBreakdown {
+        let mut rows = vec![ScoreRow::default(); groups.len()];
+
+        // Children come after their parents, so going backwards computes them first
+        for index in (0..groups.len()).rev() {
+            let group = &groups[index];
+            let Some(exec) = exec_name.iter().position(|name| *name == group.exec) else {
+                continue;
+            };
+
+            let children: Vec<usize> = groups[index + 1..]
+                .iter()
+                .take_while(|child| child.depth > group.depth)
+                .enumerate()
+                .filter(|(_, child)| child.depth == group.depth + 1)
+                .map(|(offset, _)| index + 1 + offset)
+                .collect();
+
+            let mut score = 0.0;
+            let mut max = 0.0;
+            for test in tests[exec]
+                .iter()
+                .filter(|test| group.ids.contains(&test.id))
+            {
+                if children
+                    .iter()
+                    .any(|child| groups[*child].ids.contains(&test.id))
+                {
+                    continue;
+                }
+                score += self.test_points(test);
+                max += test.test_score as f64;
+            }
+            for child in children.iter() {
+                score += rows[*child].score;
+                max += rows[*child].max;
+            }
+
+            score *= group.weight;
+            max *= group.weight;
+            if let Some(cap) = group.cap {
+                score = score.min(cap);
+                max = max.min(cap);
+            }
+
+            rows[index] = ScoreRow {
+                name: group.name.clone(),
+                depth: group.depth,
+                score,
+                max,
+            };
+        }
+
+        let tests_score: f64 = groups
+            .iter()
+            .zip(rows.iter())
+            .filter(|(group, _)| group.depth == 0)
+            .map(|(_, row)| row.score)
+            .sum();
+
+        let checkstyle = ScoreRow {
+            name: String::from("Checkstyle"),
+            depth: 0,
+            score: if checkstyle_errors <= self.checkstyle.max_errors
+                && tests_score >= self.checkstyle.min_tests_score
+            {
+                self.checkstyle.points
+            } else {
+                0.0
+            },
+            max: self.checkstyle.points,
+        };
+
+        let base = tests_score + checkstyle.score;
+        let bonuses: Vec<ScoreRow> = self
+            .bonus
+            .iter()
+            .map(|bonus| ScoreRow {
+                name: bonus.name.clone(),
+                depth: 0,
+                score: if base >= bonus.threshold {
+                    bonus.points
+                } else {
+                    0.0
+                },
+                max: bonus.points,
+            })
+            .collect();
+
+        ScoreBreakdown {
+            total: base + bonuses.iter().map(|bonus| bonus.score).sum::<f64>(),
+            max: self.max_score,
+            groups: rows,
+            checkstyle,
+            bonuses,
+        }
+    }
+}
+
+impl ScoreBreakdown {
+    /// Every row, in the order they are shown
+    pub fn rows(&self) -> impl Iterator<Item = &ScoreRow> {
+        self.groups
+            .iter()
+            .chain(Some(&self.checkstyle).filter(|row| row.max > 0.0))
+            .chain(self.bonuses.iter())
+    }
+}
+
+/// Writes points with at most two decimals, without the trailing zeros. Points that
+/// round to a whole number have no decimals.
+pub fn format_points(points: f64) -> String {
+    format!("{:.2}", points)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suite::GroupManifest;
+
+    fn test(id: usize, status: TestStatus, passed_output: bool) -> Test {
+        Test {
+            id,
+            name: format!("Test {:02}", id),
+            status,
+            log: String::new(),
+            time_normal: 0.0,
+            time_valgrind: 0.0,
+            passed_output,
+            timeout: 1000,
+            test_score: 10,
+        }
+    }
+
+    fn group(name: &str, tests: Vec<usize>, groups: Vec<GroupManifest>) -> GroupManifest {
+        GroupManifest {
+            name: name.to_string(),
+            tests,
+            groups,
+            weight: None,
+            cap: None,
+        }
+    }
+
+    #[test]
+    fn breakdown_of_nested_groups() {
+        let passed = TestStatus::Passed { points: 10.0 };
+        let tests = vec![
+            test(0, passed.clone(), true),
+            test(1, passed.clone(), true),
+            test(2, TestStatus::Failed, false),
+            test(3, TestStatus::MemLeaks, true),
+            test(4, passed, true),
+        ];
+
+        let mut doubled = group("Part 1b", vec![1], vec![]);
+        doubled.weight = Some(2.0);
+        doubled.cap = Some(15.0);
+        let manifests = vec![
+            group("Part 1", vec![0], vec![doubled]),
+            group("Part 2", vec![2, 3], vec![]),
+        ];
+
+        let mut warnings = Vec::new();
+        let mut groups = vec![Group::from_exec("exec", &tests)];
+        groups.extend(Group::flatten("exec", &manifests, &tests, 1, &mut warnings));
+        assert!(warnings.is_empty());
+
+        let scoring = Scoring {
+            valgrind: ValgrindRule { leak_credit: 0.5 },
+            bonus: vec![BonusRule {
+                name: String::from("Bonus"),
+                threshold: 50.0,
+                points: 5.0,
+            }],
+            ..Scoring::default()
+        };
+        let breakdown = scoring.breakdown(&groups, &[String::from("exec")], &[tests], 0);
+
+        let subtotals: Vec<(&str, usize, f64, f64)> = breakdown
+            .groups
+            .iter()
+            .map(|row| (row.name.as_str(), row.depth, row.score, row.max))
+            .collect();
+        assert_eq!(
+            subtotals,
+            [
+                // Test 04 is only in the executable
+                ("exec", 0, 40.0, 55.0),
+                ("Part 1", 1, 25.0, 25.0),
+                // 20 points after the weight, capped
+                ("Part 1b", 2, 15.0, 15.0),
+                // Half of the points of the leaking test
+                ("Part 2", 1, 5.0, 20.0),
+            ]
+        );
+        assert_eq!(breakdown.checkstyle.score, 10.0);
+        assert_eq!(breakdown.bonuses[0].score, 5.0);
+        assert_eq!(breakdown.total, 55.0);
+        assert_eq!(breakdown.max, 100.0);
+    }
+
+    #[test]
+    fn checkstyle_needs_few_errors_and_enough_tests() {
+        let tests = vec![test(0, TestStatus::Passed { points: 10.0 }, true)];
+        let groups = vec![Group::from_exec("exec", &tests)];
+        let exec_name = [String::from("exec")];
+        let tests = [tests];
+
+        let mut scoring = Scoring::default();
+        scoring.checkstyle.max_errors = 1;
+        let total = |scoring: &Scoring, errors| {
+            scoring.breakdown(&groups, &exec_name, &tests, errors).total
+        };
+        assert_eq!(total(&scoring, 1), 20.0);
+        assert_eq!(total(&scoring, 2), 10.0);
+
+        scoring.checkstyle.min_tests_score = 20.0;
+        assert_eq!(total(&scoring, 0), 10.0);
+    }
+
+    #[test]
+    fn points_are_written_without_trailing_zeros() {
+        assert_eq!(format_points(3.0), "3");
+        assert_eq!(format_points(2.5), "2.5");
+        assert_eq!(format_points(1.0 / 3.0), "0.33");
+        assert_eq!(format_points(0.5), "0.5");
+        assert_eq!(format_points(2.999), "3");
+        assert_eq!(format_points(10.0), "10");
+        assert_eq!(format_points(0.0), "0");
+    }
+}
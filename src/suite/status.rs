@@ -0,0 +1,229 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::format_points;
+
+/// The state of a test, from being queued to its outcome.
+///
+/// It is stored as the strings written by older versions of the checker: the
+/// points for a passed test, `"0"` for a failed one and the upper case name of
+/// the other statuses.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TestStatus {
+    /// Never run
+    #[default]
+    Pending,
+    /// Waiting for a free worker
+    Queued,
+    Running,
+    /// The output matched the ref
+    Passed {
+        points: f64,
+    },
+    /// The output did not match the ref
+    Failed,
+    Timeout,
+    /// Killed by a signal, when it is known
+    Crashed {
+        signal: Option<i32>,
+    },
+    /// The output may match the ref, but valgrind found errors
+    MemLeaks,
+    /// The test could not be run
+    Error {
+        reason: String,
+    },
+}
+
+impl TestStatus {
+    /// Queued or running
+    pub fn is_active(&self) -> bool {
+        matches!(self, Self::Queued | Self::Running)
+    }
+
+    pub fn is_passed(&self) -> bool {
+        matches!(self, Self::Passed { .. })
+    }
+
+    /// The status as stored by a previous checker. A test still queued or running
+    /// was stopped with that checker, so it never finished.
+    pub fn restored(self) -> Self {
+        if self.is_active() {
+            Self::Pending
+        } else {
+            self
+        }
+    }
+
+    /// Finished without passing, or never run
+    pub fn needs_rerun(&self) -> bool {
+        !self.is_passed() && !self.is_active()
+    }
+
+    /// Points given by the status alone
+    pub fn points(&self) -> f64 {
+        match self {
+            Self::Passed { points } => *points,
+            _ => 0.0,
+        }
+    }
+
+    /// The string stored in the results, which keeps the details of the status
+    pub fn code(&self) -> String {
+        match self {
+            Self::Pending => String::from("PENDING"),
+            Self::Queued => String::from("STARTING"),
+            Self::Running => String::from("RUNNING"),
+            // "0" is a failed test
+            Self::Passed { points } if *points == 0.0 => String::from("PASSED"),
+            Self::Passed { points } => format_points(*points),
+            Self::Failed => String::from("0"),
+            Self::Timeout => String::from("TIMEOUT"),
+            Self::Crashed { signal: None } => String::from("CRASHED"),
+            Self::Crashed {
+                signal: Some(signal),
+            } => format!("CRASHED:{}", signal),
+            Self::MemLeaks => String::from("MEMLEAKS"),
+            Self::Error { reason } if reason.is_empty() => String::from("ERROR"),
+            Self::Error { reason } => format!("ERROR:{}", reason),
+        }
+    }
+}
+
+/// The short label shown in the test list
+impl Display for TestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "-"),
+            Self::Passed { points } => write!(f, "{}", format_points(*points)),
+            Self::Crashed { .. } => write!(f, "CRASHED"),
+            Self::Error { .. } => write!(f, "ERROR"),
+            status => write!(f, "{}", status.code()),
+        }
+    }
+}
+
+impl FromStr for TestStatus {
+    type Err = std::convert::Infallible;
+
+    /// Unknown strings become an `Error`, so no result is lost
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let (name, detail) = match code.split_once(':') {
+            Some((name, detail)) => (name, Some(detail)),
+            None => (code, None),
+        };
+
+        Ok(match name {
+            "PENDING" | "" => Self::Pending,
+            "STARTING" | "QUEUED" => Self::Queued,
+            "RUNNING" => Self::Running,
+            "PASSED" => Self::Passed { points: 0.0 },
+            "TIMEOUT" => Self::Timeout,
+            "CRASHED" => Self::Crashed {
+                signal: detail.and_then(|signal| signal.parse().ok()),
+            },
+            "MEMLEAKS" => Self::MemLeaks,
+            "ERROR" => Self::Error {
+                reason: detail.unwrap_or_default().to_string(),
+            },
+            _ => match code.parse::<f64>() {
+                Ok(points) if points > 0.0 => Self::Passed { points },
+                Ok(_) => Self::Failed,
+                Err(_) => Self::Error {
+                    reason: code.to_string(),
+                },
+            },
+        })
+    }
+}
+
+impl Serialize for TestStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for TestStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Ok(code.parse().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(code: &str) -> TestStatus {
+        code.parse().unwrap()
+    }
+
+    #[test]
+    fn reads_the_legacy_strings() {
+        assert_eq!(parse("0"), TestStatus::Failed);
+        assert_eq!(parse("3"), TestStatus::Passed { points: 3.0 });
+        assert_eq!(parse("2.5"), TestStatus::Passed { points: 2.5 });
+        assert_eq!(parse("STARTING"), TestStatus::Queued);
+        assert_eq!(parse("RUNNING"), TestStatus::Running);
+        assert_eq!(parse("TIMEOUT"), TestStatus::Timeout);
+        assert_eq!(parse("MEMLEAKS"), TestStatus::MemLeaks);
+        assert_eq!(parse(""), TestStatus::Pending);
+        assert_eq!(parse("CRASHED"), TestStatus::Crashed { signal: None });
+        assert_eq!(
+            parse("CRASHED:11"),
+            TestStatus::Crashed { signal: Some(11) }
+        );
+        assert_eq!(
+            parse("ERROR:No such file"),
+            TestStatus::Error {
+                reason: String::from("No such file")
+            }
+        );
+        assert_eq!(
+            parse("garbage"),
+            TestStatus::Error {
+                reason: String::from("garbage")
+            }
+        );
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        let statuses = [
+            TestStatus::Pending,
+            TestStatus::Queued,
+            TestStatus::Running,
+            TestStatus::Passed { points: 0.0 },
+            TestStatus::Passed { points: 3.0 },
+            TestStatus::Passed { points: 1.25 },
+            TestStatus::Failed,
+            TestStatus::Timeout,
+            TestStatus::Crashed { signal: None },
+            TestStatus::Crashed { signal: Some(6) },
+            TestStatus::MemLeaks,
+            TestStatus::Error {
+                reason: String::new(),
+            },
+            TestStatus::Error {
+                reason: String::from("Cannot run: exit 127"),
+            },
+        ];
+
+        for status in statuses {
+            assert_eq!(parse(&status.code()), status, "{}", status.code());
+
+            let json = serde_json::to_string(&status).unwrap();
+            assert_eq!(serde_json::from_str::<TestStatus>(&json).unwrap(), status);
+        }
+    }
+
+    #[test]
+    fn restored_tests_are_not_active() {
+        assert_eq!(TestStatus::Queued.restored(), TestStatus::Pending);
+        assert_eq!(TestStatus::Running.restored(), TestStatus::Pending);
+        assert_eq!(TestStatus::Timeout.restored(), TestStatus::Timeout);
+        assert!(parse("RUNNING").restored().needs_rerun());
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+use super::TestStatus;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Test {
+    pub id: usize,
+    pub name: String,
+    pub status: TestStatus,
+    pub log: String,
+    pub time_normal: f64,
+    pub time_valgrind: f64,
+    /// The output matched the ref, even if valgrind found leaks
+    #[serde(default)]
+    pub passed_output: bool,
+    pub timeout: u64,
+    pub test_score: usize,
+}
+
+impl Test {
+    /// Seconds spent by the last run, the valgrind one when it is enabled and it ran
+    pub fn time(&self, valgrind: bool) -> f64 {
+        if valgrind && self.time_valgrind > 0.0 {
+            self.time_valgrind
+        } else {
+            self.time_normal
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::path::Path;
+
+use super::Suite;
+
+/// Every problem that keeps the checker from starting
+#[derive(Debug, Clone, Default)]
+pub struct InvalidSuite {
+    pub problems: Vec<String>,
+}
+
+impl Display for InvalidSuite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "The checker cannot start, found {} problem(s) with the test suite:",
+            self.problems.len()
+        )?;
+
+        for problem in self.problems.iter() {
+            let mut lines = problem.lines();
+            if let Some(first) = lines.next() {
+                writeln!(f, "  - {}", first)?;
+            }
+            for line in lines {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+
+        write!(f, "Fix them and run the checker again.")
+    }
+}
+
+impl std::error::Error for InvalidSuite {}
+
+impl From<eyre::Report> for InvalidSuite {
+    fn from(report: eyre::Report) -> Self {
+        Self {
+            problems: vec![format!("{:#}", report)],
+        }
+    }
+}
+
+/// Checks that the suite can be run, collecting all the problems instead of
+/// stopping at the first one
+pub fn validate(suite: &Suite) -> Result<(), InvalidSuite> {
+    let mut problems = Vec::new();
+    let test_path = Path::new(&suite.test_path);
+
+    if !test_path.is_dir() {
+        problems.push(format!("Test directory {} does not exist", suite.test_path));
+    } else {
+        for dir in ["input", "ref", "output"] {
+            if !test_path.join(dir).is_dir() {
+                problems.push(format!(
+                    "Directory {}{} does not exist",
+                    suite.test_path, dir
+                ));
+            }
+        }
+    }
+
+    if suite.exec_name.len() != suite.tests.len() {
+        problems.push(format!(
+            "There are {} executable names for {} lists of tests",
+            suite.exec_name.len(),
+            suite.tests.len()
+        ));
+    }
+
+    if suite.tests.iter().all(|list| list.is_empty()) {
+        problems.push(String::from("The suite has no tests"));
+    }
+
+    for (exec, list) in suite.exec_name.iter().zip(suite.tests.iter()) {
+        let mut ids = HashSet::new();
+
+        for (index, test) in list.iter().enumerate() {
+            if !ids.insert(test.id) {
+                problems.push(format!("Duplicate id {} for executable {}", test.id, exec));
+            }
+
+            // Hand-written lists are usually copy-pasted, a mismatch means the
+            // test runs the files of another one
+            if suite.legacy && test.id != index {
+                problems.push(format!(
+                    "Test \"{}\" of {} has id {}, but it is number {} in the list",
+                    test.name, exec, test.id, index
+                ));
+            }
+
+            if test.timeout == 0 {
+                problems.push(format!(
+                    "Test \"{}\" of {} has a timeout of 0",
+                    test.name, exec
+                ));
+            }
+
+            if test_path.is_dir() {
+                for (dir, extension) in [("input", "in"), ("ref", "ref")] {
+                    let file = format!("{}/{:02}-{}.{}", dir, test.id, exec, extension);
+                    if !test_path.join(&file).is_file() {
+                        problems.push(format!("Missing {}{}", suite.test_path, file));
+                    }
+                }
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(InvalidSuite { problems })
+    }
+}